@@ -0,0 +1,131 @@
+//! Scrambles private inputs for bug reports, keeping what the solvers rely on.
+//!
+//! Each supported day rewrites its own input format, the result is then solved
+//! once more to make sure it is still a valid input.
+
+use crate::solution::{self, Res};
+
+/// Small xorshift generator, reproducible from its seed
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform-ish value in `0..bound`
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Anonymise `text` as an input of `day`, checking the result still gives the same answers
+pub(crate) fn anonymise(day: u8, text: &str, seed: u64) -> Res<String> {
+    let rng = &mut Rng::new(seed);
+    let out = match day {
+        5 => crate::day05::anonymise(text, rng)?,
+        7 => crate::day07::anonymise(text, rng)?,
+        8 => crate::day08::anonymise(text, rng)?,
+        _ => return Err(format!("day {day} has no anonymiser")),
+    };
+
+    let solution = solution::find(day).ok_or_else(|| format!("day {day} is not solved"))?;
+    for part in 1..=solution.parts() {
+        // Parts failing on the original, like a sample meant for the other part, are skipped
+        let Ok(expected) = solution.solve(part, text) else {
+            continue;
+        };
+        let answer = solution
+            .solve(part, &out)
+            .map_err(|err| format!("anonymised input breaks part {part}: {err}"))?;
+        if answer != expected {
+            return Err(format!(
+                "anonymised input breaks part {part}: {expected} became {answer}"
+            ));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_first;
+
+    #[test]
+    fn rng() {
+        let draw = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..8).map(|_| rng.next()).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(0), draw(0));
+        assert_ne!(draw(0), draw(1));
+
+        let mut rng = Rng::new(3);
+        assert!((0..1000).all(|_| rng.below(7) < 7));
+        assert_eq!(rng.below(1), 0);
+        let mut items = (0..50).collect::<Vec<_>>();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+        rng.shuffle(&mut [0u8; 0]);
+    }
+
+    #[test]
+    fn answers() {
+        let samples = [
+            (5, "src/day05/sample.txt"),
+            (7, "src/day07/sample.txt"),
+            (8, "src/day08/sample1.txt"),
+            (8, "src/day08/sample2.txt"),
+            (8, "src/day08/sample3.txt"),
+        ];
+        for (day, path) in samples {
+            let text = open_first(&[path]).unwrap();
+            let solution = solution::find(day).unwrap();
+            for seed in 0..5 {
+                let out = anonymise(day, &text, seed).unwrap();
+                assert_ne!(out, text, "{path} seed {seed}");
+                for part in 1..=2 {
+                    if let Ok(expected) = solution.solve(part, &text) {
+                        assert_eq!(
+                            solution.solve(part, &out),
+                            Ok(expected),
+                            "{path} seed {seed}"
+                        );
+                    }
+                }
+            }
+        }
+        // Equal hands rank by line, so they must be told apart
+        let equal = "32T3K 765\n32T3K 684\nKK677 28\nKK677 220\n";
+        let day07 = solution::find(7).unwrap();
+        for seed in 0..5 {
+            let out = anonymise(7, equal, seed).unwrap();
+            let mut hands = out.lines().map(|line| &line[..5]).collect::<Vec<_>>();
+            hands.sort();
+            hands.dedup();
+            assert_eq!(hands.len(), 4, "{out}");
+            assert_eq!(day07.solve(1, &out), day07.solve(1, equal));
+            assert_eq!(day07.solve(2, &out), day07.solve(2, equal));
+        }
+        assert!(anonymise(8, "LR\n\nAAA = (BBB, CCC)\n", 0).is_err());
+        assert!(anonymise(5, "seeds: 1 2 3\n", 0).is_err());
+        assert!(anonymise(7, "32T3K\n", 0).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Arbitrary precision integers, and the [`Number`] trait counting days are generic over.
+//!
+//! Machine integers stay the default, a day switches to [`BigInt`] through its
+//! type parameter when its counts outgrow 64 bits, picked by the `number` setting.
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::iter::Sum;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::checked::Checked;
+use crate::solution::Answer;
+
+/// Signed integer of any size, base 2^32 limbs lowest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct BigInt {
+    // Zero has no limbs and is never negative
+    neg: bool,
+    mag: Vec<u32>,
+}
+
+fn trim(mut mag: Vec<u32>) -> Vec<u32> {
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    mag
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0;
+    for (i, &limb) in long.iter().enumerate() {
+        let sum = limb as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+    out.push(carry as u32);
+    trim(out)
+}
+
+/// `a - b` for `a >= b`
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let (diff, under1) = limb.overflowing_sub(*b.get(i).unwrap_or(&0));
+        let (diff, under2) = diff.overflowing_sub(borrow);
+        out.push(diff);
+        borrow = (under1 || under2) as u32;
+    }
+    trim(out)
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let cur = x as u64 * y as u64 + out[i + j] as u64 + carry;
+            out[i + j] = cur as u32;
+            carry = cur >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    trim(out)
+}
+
+impl BigInt {
+    fn new(neg: bool, mag: Vec<u32>) -> Self {
+        let mag = trim(mag);
+        Self {
+            neg: neg && !mag.is_empty(),
+            mag,
+        }
+    }
+
+    fn from_parts(neg: bool, mut abs: u128) -> Self {
+        let mut mag = Vec::new();
+        while abs != 0 {
+            mag.push(abs as u32);
+            abs >>= 32;
+        }
+        Self::new(neg, mag)
+    }
+
+    /// Sum of two signed magnitudes
+    fn signed_add(neg: bool, a: &[u32], other_neg: bool, b: &[u32]) -> Self {
+        if neg == other_neg {
+            return Self::new(neg, add_mag(a, b));
+        }
+        match cmp_mag(a, b) {
+            Ordering::Less => Self::new(other_neg, sub_mag(b, a)),
+            _ => Self::new(neg, sub_mag(a, b)),
+        }
+    }
+}
+
+macro_rules! bigint_from {
+    ($($typ:ty),*) => {
+        $(impl From<$typ> for BigInt {
+            fn from(value: $typ) -> Self {
+                #[allow(unused_comparisons)]
+                let neg = value < 0;
+                Self::from_parts(neg, (value as i128).unsigned_abs())
+            }
+        })*
+    };
+}
+
+bigint_from!(u8, u32, i32, u64, i64, usize, isize, i128);
+
+impl From<u128> for BigInt {
+    fn from(value: u128) -> Self {
+        Self::from_parts(false, value)
+    }
+}
+
+impl Add for BigInt {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::signed_add(self.neg, &self.mag, rhs.neg, &rhs.mag)
+    }
+}
+
+impl Sub for BigInt {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::signed_add(self.neg, &self.mag, !rhs.neg, &rhs.mag)
+    }
+}
+
+impl Mul for BigInt {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.neg != rhs.neg, mul_mag(&self.mag, &rhs.mag))
+    }
+}
+
+impl Neg for BigInt {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(!self.neg, self.mag)
+    }
+}
+
+impl Sum for BigInt {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, value| acc + value)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.neg, other.neg) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &other.mag),
+            (true, true) => cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const CHUNK: u64 = 1_000_000_000;
+        // Base 10^9 digits lowest first, by long division of the magnitude
+        let mut rest = self.mag.clone();
+        let mut chunks = Vec::new();
+        while !rest.is_empty() {
+            let mut rem = 0;
+            for limb in rest.iter_mut().rev() {
+                let cur = (rem << 32) | *limb as u64;
+                *limb = (cur / CHUNK) as u32;
+                rem = cur % CHUNK;
+            }
+            chunks.push(rem);
+            rest = trim(rest);
+        }
+        let mut digits = String::new();
+        match chunks.split_last() {
+            None => digits.push('0'),
+            Some((top, lower)) => {
+                digits += &top.to_string();
+                for chunk in lower.iter().rev() {
+                    digits += &format!("{chunk:09}");
+                }
+            }
+        }
+        f.pad_integral(!self.neg, "", &digits)
+    }
+}
+
+/// Integer a counting day can be generic over
+pub(crate) trait Number: Checked + Ord + Sum + Into<Answer> {
+    /// None if `value` does not fit, like a negative value for an unsigned type
+    fn from_i64(value: i64) -> Option<Self>;
+
+    /// None if `value` does not fit
+    fn from_usize(value: usize) -> Option<Self>;
+
+    fn zero() -> Self {
+        Self::from(0)
+    }
+}
+
+macro_rules! number {
+    ($($typ:ty),*) => {
+        $(impl Number for $typ {
+            fn from_i64(value: i64) -> Option<Self> {
+                value.try_into().ok()
+            }
+
+            fn from_usize(value: usize) -> Option<Self> {
+                value.try_into().ok()
+            }
+        })*
+    };
+}
+
+number!(u64, i64, usize, isize, i128);
+
+impl Number for BigInt {
+    fn from_i64(value: i64) -> Option<Self> {
+        Some(value.into())
+    }
+
+    fn from_usize(value: usize) -> Option<Self> {
+        Some(value.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(value: i128) -> BigInt {
+        value.into()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let max = BigInt::from(u64::MAX);
+        assert_eq!((max.clone() + big(1)).to_string(), "18446744073709551616");
+        assert_eq!(
+            (max.clone() * max.clone()).to_string(),
+            "340282366920938463426481119284349108225"
+        );
+        assert_eq!((big(5) - big(8)).to_string(), "-3");
+        assert_eq!((big(-5) * big(3)).to_string(), "-15");
+        assert_eq!(big(-7) + big(7), BigInt::default());
+        assert_eq!(big(0).to_string(), "0");
+        assert_eq!(big(1_000_000_000).to_string(), "1000000000");
+        assert_eq!(format!("{:>5}", big(-42)), "  -42");
+
+        // 30! does not fit 64 bits
+        let fact = (1..=30).map(big).fold(big(1), |acc, n| acc * n);
+        assert_eq!(fact.to_string(), "265252859812191058636308480000000");
+        assert_eq!(
+            (fact.clone() - fact.clone() * big(2)).to_string(),
+            "-265252859812191058636308480000000"
+        );
+
+        assert!(big(-3) < big(2));
+        assert!(big(-3) < big(-2));
+        assert!(max.clone() * big(2) > max);
+        assert_eq!(
+            [big(1), big(2), big(-4)].into_iter().sum::<BigInt>(),
+            big(-1)
+        );
+
+        assert_eq!(u64::from_i64(-1), None);
+        assert_eq!(i64::from_usize(usize::MAX), None);
+        assert_eq!(BigInt::from_i64(-1), Some(big(-1)));
+    }
+}
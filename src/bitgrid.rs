@@ -0,0 +1,233 @@
+//! Binary grid packed one row per machine word, bit `x` of a row is column `x`.
+//!
+//! Comparing rows, or a row with its own mirror image, is then a xor and a
+//! popcount instead of a loop over cells.
+
+use std::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+
+use crate::coord::Pos;
+
+pub(crate) trait Word:
+    Copy
+    + Eq
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<usize, Output = Self>
+    + Shr<usize, Output = Self>
+{
+    const BITS: usize;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn count_ones(self) -> u32;
+    fn reverse_bits(self) -> Self;
+
+    /// The lowest `n` bits set
+    fn mask(n: usize) -> Self {
+        if n >= Self::BITS {
+            !Self::ZERO
+        } else {
+            !(!Self::ZERO << n)
+        }
+    }
+}
+
+macro_rules! word {
+    ($($ty:ty),*) => {$(
+        impl Word for $ty {
+            const BITS: usize = <$ty>::BITS as usize;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn count_ones(self) -> u32 {
+                <$ty>::count_ones(self)
+            }
+
+            fn reverse_bits(self) -> Self {
+                <$ty>::reverse_bits(self)
+            }
+        }
+    )*};
+}
+
+word!(u64, u128);
+
+/// Number of differing bits
+pub(crate) fn diff<W: Word>(a: W, b: W) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct BitGrid<W = u64> {
+    cols: usize,
+    rows: Vec<W>,
+}
+
+impl<W: Word> BitGrid<W> {
+    /// Grid of `lines`, `one` marks a set bit and `zero` a clear one
+    pub(crate) fn parse<'a>(
+        lines: impl IntoIterator<Item = &'a str>,
+        one: char,
+        zero: char,
+    ) -> Result<Self, String> {
+        let mut cols = None;
+        let mut rows = Vec::new();
+        for line in lines {
+            let width = *cols.get_or_insert(line.chars().count());
+            if width != line.chars().count() {
+                return Err(format!("line '{line}' is not {width} wide"));
+            }
+            if width > W::BITS {
+                return Err(format!("{width} columns do not fit {} bits", W::BITS));
+            }
+            let mut row = W::ZERO;
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    _ if c == one => row = row | W::ONE << x,
+                    _ if c == zero => (),
+                    _ => return Err(format!("unexpected '{c}' in '{line}'")),
+                }
+            }
+            rows.push(row);
+        }
+        Ok(Self {
+            cols: cols.unwrap_or(0),
+            rows,
+        })
+    }
+
+    pub(crate) fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub(crate) fn rows(&self) -> &[W] {
+        &self.rows
+    }
+
+    pub(crate) fn get(&self, pos: Pos) -> bool {
+        pos.x < self.cols
+            && self
+                .rows
+                .get(pos.y)
+                .is_some_and(|&row| row >> pos.x & W::ONE == W::ONE)
+    }
+
+    /// Columns as rows, by swapping ever smaller blocks of a square of words.
+    /// The grid must be at most `W::BITS` rows high.
+    pub(crate) fn transpose(&self) -> Result<Self, String> {
+        if self.rows.len() > W::BITS {
+            return Err(format!(
+                "{} rows do not fit {} bits",
+                self.rows.len(),
+                W::BITS
+            ));
+        }
+        let mut out = self.rows.clone();
+        out.resize(W::BITS, W::ZERO);
+        let mut size = W::BITS / 2;
+        while size > 0 {
+            // Columns of the left block of every pair, like 0x0f0f.. for size 4
+            let mut left = W::mask(size);
+            let mut width = 2 * size;
+            while width < W::BITS {
+                left = left | left << width;
+                width *= 2;
+            }
+            // Swap the top right block of each pair with the bottom left one
+            for y in (0..W::BITS).filter(|y| y & size == 0) {
+                let swap = (out[y] >> size ^ out[y + size]) & left;
+                out[y] = out[y] ^ swap << size;
+                out[y + size] = out[y + size] ^ swap;
+            }
+            size /= 2;
+        }
+        out.truncate(self.cols);
+        Ok(Self {
+            cols: self.rows.len(),
+            rows: out,
+        })
+    }
+
+    /// Cells differing between `row` and its mirror image around the line
+    /// left of column `at`, over the columns both sides have
+    pub(crate) fn mirror_diff(&self, row: W, at: usize) -> u32 {
+        let width = at.min(self.cols - at);
+        // Columns at - 1, at - 2, .. from bit 0 up
+        let left = row.reverse_bits() >> (W::BITS - at);
+        let right = row >> at;
+        diff(left & W::mask(width), right & W::mask(width))
+    }
+
+    /// Cells differing between the grid and its mirror image around the
+    /// line left of column `at`, with `0 < at < cols`
+    pub(crate) fn vertical_mirror_diff(&self, at: usize) -> u32 {
+        self.rows.iter().map(|&row| self.mirror_diff(row, at)).sum()
+    }
+}
+
+impl<W: Word> std::fmt::Display for BitGrid<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.rows.len() {
+            let line = (0..self.cols)
+                .map(|x| if self.get(Pos::new(x, y)) { '#' } else { '.' })
+                .collect::<String>();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words() {
+        let grid = BitGrid::<u64>::parse(["#..#.", ".##..", "....#"], '#', '.').unwrap();
+        assert!(grid.get(Pos::new(3, 0)));
+        assert!(!grid.get(Pos::new(3, 1)));
+        assert_eq!(grid.rows(), [0b01001, 0b00110, 0b10000]);
+        assert_eq!(diff(grid.rows()[0], grid.rows()[1]), 4);
+
+        let t = grid.transpose().unwrap();
+        assert_eq!(t.cols(), 3);
+        assert_eq!(t.rows(), [0b001, 0b010, 0b010, 0b001, 0b100]);
+        assert_eq!(t.transpose(), Ok(grid.clone()));
+        assert_eq!(grid.to_string(), "#..#.\n.##..\n....#\n");
+
+        // ".##.." mirrors around the line between columns 1 and 2
+        let row = grid.rows()[1];
+        assert_eq!(grid.mirror_diff(row, 2), 0);
+        assert_eq!(grid.mirror_diff(row, 1), 1);
+        assert_eq!(grid.vertical_mirror_diff(2), 0);
+        assert_eq!(grid.vertical_mirror_diff(1), 2);
+
+        let wide = BitGrid::<u128>::parse(["#".repeat(100).as_str()], '#', '.').unwrap();
+        assert_eq!(wide.mirror_diff(wide.rows()[0], 30), 0);
+        assert!(BitGrid::<u64>::parse(["#".repeat(100).as_str()], '#', '.').is_err());
+
+        // A full square and a grid too high for its words
+        let lines = (0..128)
+            .map(|y| {
+                (0..100)
+                    .map(|x| if (x * y) % 7 == 1 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect::<Vec<String>>();
+        let square = BitGrid::<u128>::parse(lines.iter().map(String::as_str), '#', '.').unwrap();
+        let t = square.transpose().unwrap();
+        assert_eq!((t.cols(), t.rows().len()), (128, 100));
+        assert!(
+            (0..100).all(|x| (0..128).all(|y| t.get(Pos::new(y, x)) == square.get(Pos::new(x, y))))
+        );
+        assert_eq!(t.transpose(), Ok(square));
+        let high = vec!["#"; 65];
+        assert!(BitGrid::<u64>::parse(high, '#', '.')
+            .unwrap()
+            .transpose()
+            .is_err());
+        assert!(BitGrid::<u64>::parse(["#.", "#"], '#', '.').is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Overflow checked answer arithmetic, switched on by the `checked` setting.
+//!
+//! Outside checked mode the operations are the plain operators, so a release
+//! build wraps as before. In checked mode an overflow is an error naming the
+//! operation and its operands, which the runner reports with the day and part.
+//!
+//! Every sum, product and difference making up an answer goes through here;
+//! index and coordinate arithmetic within the puzzle does not.
+
+use std::cell::Cell;
+use std::fmt::Display;
+use std::ops::{Add, Mul, Sub};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::bigint::BigInt;
+use crate::solution::Res;
+
+static CHECKED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Overrides the global mode on one thread, so tests do not race
+    static SCOPED: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+pub(crate) fn set_enabled(enabled: bool) {
+    CHECKED.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn enabled() -> bool {
+    SCOPED
+        .get()
+        .unwrap_or_else(|| CHECKED.load(Ordering::Relaxed))
+}
+
+/// Sets the mode on this thread until dropped
+#[cfg(test)]
+pub(crate) struct Scoped(Option<bool>);
+
+#[cfg(test)]
+pub(crate) fn scoped(enabled: bool) -> Scoped {
+    Scoped(SCOPED.replace(Some(enabled)))
+}
+
+#[cfg(test)]
+impl Drop for Scoped {
+    fn drop(&mut self) {
+        SCOPED.set(self.0);
+    }
+}
+
+pub(crate) trait Checked:
+    Clone + Display + From<u8> + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const NAME: &'static str;
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+}
+
+macro_rules! checked {
+    ($($typ:ty),*) => {
+        $(impl Checked for $typ {
+            const NAME: &'static str = stringify!($typ);
+
+            fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                <$typ>::checked_add(*self, *rhs)
+            }
+
+            fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                <$typ>::checked_sub(*self, *rhs)
+            }
+
+            fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                <$typ>::checked_mul(*self, *rhs)
+            }
+        })*
+    };
+}
+
+checked!(u32, i32, u64, i64, usize, isize, i128);
+
+// Never overflows, so checked mode costs only the clones
+impl Checked for BigInt {
+    const NAME: &'static str = "BigInt";
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self.clone() + rhs.clone())
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self.clone() - rhs.clone())
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self.clone() * rhs.clone())
+    }
+}
+
+fn apply<T: Checked>(
+    what: &str,
+    (a, op, b): (T, &str, T),
+    checked: fn(&T, &T) -> Option<T>,
+    plain: fn(T, T) -> T,
+) -> Res<T> {
+    if !enabled() {
+        return Ok(plain(a, b));
+    }
+    checked(&a, &b).ok_or_else(|| format!("{what} overflows {}: {a} {op} {b}", T::NAME))
+}
+
+/// `a + b`, `what` names the operation in the error
+pub(crate) fn add<T: Checked>(a: T, b: T, what: &str) -> Res<T> {
+    apply(what, (a, "+", b), T::checked_add, T::add)
+}
+
+/// `a - b`, `what` names the operation in the error
+pub(crate) fn sub<T: Checked>(a: T, b: T, what: &str) -> Res<T> {
+    apply(what, (a, "-", b), T::checked_sub, T::sub)
+}
+
+/// `a * b`, `what` names the operation in the error
+pub(crate) fn mul<T: Checked>(a: T, b: T, what: &str) -> Res<T> {
+    apply(what, (a, "*", b), T::checked_mul, T::mul)
+}
+
+/// Sum of `values`, stopping at the first error
+pub(crate) fn sum<T: Checked>(values: impl IntoIterator<Item = Res<T>>, what: &str) -> Res<T> {
+    values
+        .into_iter()
+        .try_fold(T::from(0), |sum, value| add(sum, value?, what))
+}
+
+/// Product of `values`, stopping at the first error
+pub(crate) fn product<T: Checked>(values: impl IntoIterator<Item = Res<T>>, what: &str) -> Res<T> {
+    values
+        .into_iter()
+        .try_fold(T::from(1), |product, value| mul(product, value?, what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow() {
+        let guard = scoped(true);
+        assert_eq!(mul(70_000u32, 60_000, "distance"), Ok(4_200_000_000));
+        assert_eq!(
+            mul(70_000u32, 70_000, "distance"),
+            Err("distance overflows u32: 70000 * 70000".to_string())
+        );
+        assert_eq!(
+            sub(1usize, 2, "remainder"),
+            Err("remainder overflows usize: 1 - 2".to_string())
+        );
+        assert_eq!(sum([Ok(1u64), Ok(2), Ok(3)], "total"), Ok(6));
+        assert!(product([Ok(u64::MAX), Ok(2)], "power").is_err());
+        assert_eq!(
+            sum([Ok(1i64), Err("bad".to_string())], "total"),
+            Err("bad".to_string())
+        );
+        assert_eq!(
+            mul(BigInt::from(u64::MAX), BigInt::from(2u8), "power").map(|n| n.to_string()),
+            Ok("36893488147419103230".to_string())
+        );
+        drop(guard);
+
+        let _guard = scoped(false);
+        assert_eq!(add(1u32, 2, "total"), Ok(3));
+        assert_eq!(mul(u32::MAX, 1, "total"), Ok(u32::MAX));
+    }
+}
@@ -0,0 +1,350 @@
+//! Runner settings, layered from defaults, config files and command line flags.
+//!
+//! Later layers win: defaults, the user config directory, the project root,
+//! then flags. Every value remembers where it came from for `config show`.
+//! Relative paths are taken from the project root, whatever the current directory.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::solution::{self, Res, Settings};
+
+const FILE_NAME: &str = "aoc.toml";
+
+/// Known top level settings with a short description
+const SETTINGS: &[(&str, &str)] = &[
+    ("input", "input path, {day} is the zero padded day"),
+    ("sample", "preferred sample, used when the input is missing"),
+    ("verbosity", "0: answers, 1: timings, 2: input paths"),
+    ("threads", "worker threads when running several parts"),
+    ("session", "file holding the session token"),
+    ("time_limit", "seconds before a part times out, 0 for none"),
+    ("format", "output format, text or json"),
+    ("checked", "report arithmetic overflow in answers as errors"),
+    (
+        "explain",
+        "print how each part was solved, for days that can",
+    ),
+];
+
+/// Settings a bare flag switches on, `--checked` for `--checked=true`
+const SWITCHES: &[&str] = &["checked", "explain"];
+
+#[derive(Debug, Clone)]
+pub(crate) enum Source {
+    Default,
+    File(PathBuf, usize),
+    Flag,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path, line) => write!(f, "{}:{}", path.display(), line),
+            Self::Flag => write!(f, "command line"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Config {
+    values: BTreeMap<String, (String, Source)>,
+}
+
+/// The project root, where `aoc.toml`, the answers and the inputs live
+pub(crate) fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn user_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("aoc"))
+}
+
+fn known(key: &str) -> bool {
+    match key.split_once('.') {
+        // Sectioned keys belong to a single day, like `day02.bag`
+        Some((section, name)) => solution::SOLUTIONS.iter().any(|solution| {
+            section == format!("day{:02}", solution.day()) && solution.settings().contains(&name)
+        }),
+        None => SETTINGS.iter().any(|(name, _)| *name == key),
+    }
+}
+
+/// Parse the small subset of TOML used by the config, `key = value` lines under optional `[section]`s
+pub(crate) fn parse_toml(text: &str) -> Res<Vec<(String, String, usize)>> {
+    let mut section = String::new();
+    let mut out = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .split_once(']')
+                .ok_or_else(|| format!("line {}: unclosed section", idx + 1))?
+                .0;
+            section = format!("{}.", name.trim());
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected key = value", idx + 1))?;
+        let value = value.trim();
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let mut out = String::new();
+            let mut chars = quoted.chars();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => out.extend(chars.next()),
+                    Some(c) => out.push(c),
+                    None => return Err(format!("line {}: unclosed string", idx + 1)),
+                }
+            }
+            out
+        } else {
+            value
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        out.push((format!("{section}{}", key.trim()), value, idx + 1));
+    }
+    Ok(out)
+}
+
+impl Config {
+    fn defaults() -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let session = user_dir()
+            .map(|dir| dir.join("session").display().to_string())
+            .unwrap_or_else(|| ".session".to_string());
+        let values = [
+            ("input", "src/day{day}/input.txt".to_string()),
+            ("sample", "src/day{day}/sample.txt".to_string()),
+            ("verbosity", "1".to_string()),
+            ("threads", threads.to_string()),
+            ("session", session),
+            ("time_limit", "0".to_string()),
+            ("format", "text".to_string()),
+            ("checked", "false".to_string()),
+            ("explain", "false".to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), (value, Source::Default)))
+        .collect();
+        Self { values }
+    }
+
+    fn load_file(&mut self, path: &Path) -> Res<()> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(format!("{}: {err}", path.display())),
+        };
+        for (key, value, line) in
+            parse_toml(&text).map_err(|e| format!("{}: {e}", path.display()))?
+        {
+            if !known(&key) {
+                return Err(format!(
+                    "{}:{line}: unknown setting '{key}'",
+                    path.display()
+                ));
+            }
+            let source = Source::File(path.to_path_buf(), line);
+            self.values.insert(key, (value, source));
+        }
+        Ok(())
+    }
+
+    /// Remove the setting flags from `args`, leaving the positional arguments
+    fn apply_flags(&mut self, args: &mut Vec<String>) -> Res<()> {
+        let mut rest = Vec::with_capacity(args.len());
+        let mut iter = std::mem::take(args).into_iter();
+        while let Some(arg) = iter.next() {
+            if arg == "-v" {
+                let verbosity = self.parse::<u8>("verbosity")?;
+                self.set("verbosity", (verbosity + 1).to_string());
+                continue;
+            }
+            if let Some(&switch) = SWITCHES.iter().find(|&&name| arg == format!("--{name}")) {
+                self.set(switch, "true".to_string());
+                continue;
+            }
+            let Some(flag) = arg.strip_prefix("--") else {
+                rest.push(arg);
+                continue;
+            };
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+            let key = name.replace('-', "_");
+            if !known(&key) && key.contains('.') {
+                return Err(format!("unknown setting '{key}'"));
+            }
+            if !known(&key) {
+                rest.push(arg);
+                continue;
+            }
+            let value = value
+                .or_else(|| iter.next())
+                .ok_or_else(|| format!("missing value for --{name}"))?;
+            self.set(&key, value);
+        }
+        *args = rest;
+        Ok(())
+    }
+
+    /// Layer defaults, config files and the flags taken out of `args`
+    pub(crate) fn load(args: &mut Vec<String>) -> Res<Self> {
+        let mut config = Self::defaults();
+        if let Some(dir) = user_dir() {
+            config.load_file(&dir.join(FILE_NAME))?;
+        }
+        config.load_file(&root().join(FILE_NAME))?;
+        config.apply_flags(args)?;
+        Ok(config)
+    }
+
+    pub(crate) fn set(&mut self, key: &str, value: String) {
+        self.values.insert(key.to_string(), (value, Source::Flag));
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|(value, _)| value.as_str())
+    }
+
+    pub(crate) fn parse<T: FromStr>(&self, key: &str) -> Res<T>
+    where
+        T::Err: std::fmt::Display,
+    {
+        let (value, source) = self
+            .values
+            .get(key)
+            .ok_or_else(|| format!("missing setting '{key}'"))?;
+        value
+            .parse()
+            .map_err(|e| format!("invalid {key} '{value}' ({source}): {e}"))
+    }
+
+    /// The `time_limit` setting, which must be a finite number of seconds, not negative
+    pub(crate) fn time_limit(&self) -> Res<Duration> {
+        let secs = self.parse::<f64>("time_limit")?;
+        Duration::try_from_secs_f64(secs).map_err(|e| {
+            let source = &self.values["time_limit"].1;
+            format!("invalid time_limit '{secs}' ({source}): {e}")
+        })
+    }
+
+    /// Settings of the `[dayNN]` section, without the prefix
+    pub(crate) fn section(&self, day: u8) -> Settings {
+        let prefix = format!("day{day:02}.");
+        self.values
+            .iter()
+            .filter_map(|(key, (value, _))| Some((key.strip_prefix(&prefix)?, value.as_str())))
+            .collect()
+    }
+
+    /// Path of a per day setting, with `{day}` filled in
+    pub(crate) fn day_path(&self, key: &str, day: u8) -> PathBuf {
+        let template = self.get(key).unwrap_or_default();
+        root().join(template.replace("{day}", &format!("{day:02}")))
+    }
+
+    /// Table of the effective settings and where each came from
+    pub(crate) fn show(&self) -> String {
+        let rows = self
+            .values
+            .iter()
+            .map(|(key, (value, source))| {
+                let help = SETTINGS
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map_or("", |(_, help)| help);
+                (key, format!("{value:?}"), source.to_string(), help)
+            })
+            .collect::<Vec<_>>();
+        let key_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+        let value_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0);
+        let source_width = rows.iter().map(|row| row.2.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for (key, value, source, help) in rows {
+            let line = format!(
+                "{key:key_width$} = {value:value_width$}  # {source:source_width$}  {help}"
+            );
+            out += line.trim_end();
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layering() {
+        let mut config = Config::defaults();
+        let text =
+            "# comment\nthreads = 3\nformat = \"json\" # trailing\n[day02]\nbag = \"red=1\"\n";
+        for (key, value, line) in parse_toml(text).unwrap() {
+            config
+                .values
+                .insert(key, (value, Source::File("aoc.toml".into(), line)));
+        }
+        assert_eq!(config.get("format"), Some("json"));
+        assert_eq!(config.get("day02.bag"), Some("red=1"));
+
+        let mut args = [
+            "run",
+            "--threads",
+            "5",
+            "-v",
+            "--checked",
+            "--time-limit=2",
+            "5",
+            "--explain",
+            "--day02.bag=red=3",
+        ]
+        .map(String::from)
+        .to_vec();
+        config.apply_flags(&mut args).unwrap();
+        assert_eq!(args, ["run", "5"]);
+        assert_eq!(config.parse::<usize>("threads"), Ok(5));
+        assert_eq!(config.parse::<u8>("verbosity"), Ok(2));
+        assert_eq!(config.get("time_limit"), Some("2"));
+        assert_eq!(config.parse::<bool>("checked"), Ok(true));
+        assert_eq!(config.parse::<bool>("explain"), Ok(true));
+        assert_eq!(config.section(2), Settings::from_iter([("bag", "red=3")]));
+        assert_eq!(config.section(3), Settings::default());
+        assert!(matches!(config.values["format"].1, Source::File(_, 3)));
+        assert_eq!(
+            config.day_path("input", 5),
+            root().join("src/day05/input.txt")
+        );
+
+        // Sections must name a solved day and one of its settings
+        assert!(known("day04.cap") && known("threads") && known("session"));
+        assert!(!known("day2.bag") && !known("day02.bags") && !known("day99.bag"));
+        let mut args = vec!["--day02.colour=red".to_string()];
+        assert!(config.apply_flags(&mut args).is_err());
+
+        for limit in ["-1", "NaN", "inf"] {
+            config.set("time_limit", limit.to_string());
+            assert!(config.time_limit().is_err());
+        }
+        config.set("time_limit", "0.5".to_string());
+        assert_eq!(config.time_limit(), Ok(Duration::from_millis(500)));
+    }
+}
@@ -0,0 +1,316 @@
+//! Grid coordinates and compass directions shared by the grid days.
+//!
+//! `x` grows to the right and `y` downwards, so north is `y - 1`. A [`Pos`]
+//! indexes a grid and can't go below zero, a [`Point`] is signed for offsets,
+//! polygons and positions that may leave the grid.
+
+use std::ops::{Add, Neg, Sub};
+
+/// Position on a grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub(crate) struct Pos {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+}
+
+/// Signed point or offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub(crate) struct Point {
+    pub(crate) x: i64,
+    pub(crate) y: i64,
+}
+
+/// The rectangle `0..cols` by `0..rows`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Bounds {
+    pub(crate) cols: usize,
+    pub(crate) rows: usize,
+}
+
+pub(crate) trait Direction: Copy {
+    /// Offset of a single step
+    fn offset(self) -> Point;
+}
+
+/// The four directions, in clockwise order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Dir4 {
+    N,
+    E,
+    S,
+    W,
+}
+
+/// The eight directions, in clockwise order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Dir8 {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Dir4 {
+    pub(crate) const ALL: [Self; 4] = [Self::N, Self::E, Self::S, Self::W];
+
+    fn turn(self, quarters: usize) -> Self {
+        Self::ALL[(self as usize + quarters) % 4]
+    }
+
+    // Only tests turn so far, day10 just reverses
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn turn_right(self) -> Self {
+        self.turn(1)
+    }
+
+    pub(crate) fn opposite(self) -> Self {
+        self.turn(2)
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn turn_left(self) -> Self {
+        self.turn(3)
+    }
+}
+
+impl Dir8 {
+    pub(crate) const ALL: [Self; 8] = [
+        Self::N,
+        Self::NE,
+        Self::E,
+        Self::SE,
+        Self::S,
+        Self::SW,
+        Self::W,
+        Self::NW,
+    ];
+
+    // No day turns diagonally yet
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn turn(self, eighths: usize) -> Self {
+        Self::ALL[(self as usize + eighths) % 8]
+    }
+
+    /// An eighth turn clockwise
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn turn_right(self) -> Self {
+        self.turn(1)
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn opposite(self) -> Self {
+        self.turn(4)
+    }
+
+    /// An eighth turn counterclockwise
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn turn_left(self) -> Self {
+        self.turn(7)
+    }
+}
+
+impl Direction for Dir4 {
+    fn offset(self) -> Point {
+        Dir8::from(self).offset()
+    }
+}
+
+impl Direction for Dir8 {
+    fn offset(self) -> Point {
+        let (x, y) = match self {
+            Self::N => (0, -1),
+            Self::NE => (1, -1),
+            Self::E => (1, 0),
+            Self::SE => (1, 1),
+            Self::S => (0, 1),
+            Self::SW => (-1, 1),
+            Self::W => (-1, 0),
+            Self::NW => (-1, -1),
+        };
+        Point { x, y }
+    }
+}
+
+impl From<Dir4> for Dir8 {
+    fn from(dir: Dir4) -> Self {
+        Self::ALL[dir as usize * 2]
+    }
+}
+
+impl Pos {
+    pub(crate) const fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    /// Position of the `index`th cell of a grid stored row by row
+    pub(crate) fn from_index(index: usize, cols: usize) -> Self {
+        Self::new(index % cols, index / cols)
+    }
+
+    pub(crate) fn index(self, cols: usize) -> usize {
+        self.x + self.y * cols
+    }
+
+    /// One step towards `dir`, `None` when that leaves `bounds`
+    pub(crate) fn checked_step(self, dir: impl Direction, bounds: Bounds) -> Option<Self> {
+        bounds.pos(Point::from(self) + dir.offset())
+    }
+
+    /// One step towards `dir`, coming back in on the other side when leaving `bounds`
+    // Kept for wrapping grids, no day wraps yet
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn wrapping_step(self, dir: impl Direction, bounds: Bounds) -> Self {
+        let Point { x, y } = Point::from(self) + dir.offset();
+        Self::new(
+            x.rem_euclid(bounds.cols as i64) as usize,
+            y.rem_euclid(bounds.rows as i64) as usize,
+        )
+    }
+
+    /// Positions one step away in every direction of `dirs`, within `bounds`
+    pub(crate) fn neighbours<D: Direction>(
+        self,
+        dirs: impl IntoIterator<Item = D>,
+        bounds: Bounds,
+    ) -> impl Iterator<Item = Self> {
+        dirs.into_iter()
+            .filter_map(move |dir| self.checked_step(dir, bounds))
+    }
+}
+
+impl Point {
+    // Only geometry tests build points by hand
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Bounds {
+    pub(crate) fn contains(self, point: Point) -> bool {
+        (0..self.cols as i64).contains(&point.x) && (0..self.rows as i64).contains(&point.y)
+    }
+
+    /// `point` as a position, if within the bounds
+    pub(crate) fn pos(self, point: Point) -> Option<Pos> {
+        self.contains(point)
+            .then(|| Pos::new(point.x as usize, point.y as usize))
+    }
+}
+
+impl From<Pos> for Point {
+    fn from(pos: Pos) -> Self {
+        Self {
+            x: pos.x as i64,
+            y: pos.y as i64,
+        }
+    }
+}
+
+impl Add for Point {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl Neg for Point {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl Add for Pos {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+/// Panics below zero like `usize`, go through [`Point`] when that may happen
+impl Sub for Pos {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// A position reached by moving towards `dir`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Step {
+    pub(crate) pos: Pos,
+    pub(crate) dir: Dir4,
+}
+
+impl Step {
+    pub(crate) fn checked_step(self, dir: Dir4, bounds: Bounds) -> Option<Self> {
+        let pos = self.pos.checked_step(dir, bounds)?;
+        Some(Self { pos, dir })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns() {
+        assert_eq!(Dir4::N.turn_right(), Dir4::E);
+        assert_eq!(Dir4::N.turn_left(), Dir4::W);
+        assert_eq!(Dir4::W.opposite(), Dir4::E);
+        assert_eq!(Dir8::NW.turn_right(), Dir8::N);
+        assert_eq!(Dir8::N.turn_left(), Dir8::NW);
+        assert_eq!(Dir8::SE.opposite(), Dir8::NW);
+        for dir in Dir4::ALL {
+            assert_eq!(dir.opposite().offset(), -dir.offset());
+            assert_eq!(dir.turn_left().turn_right(), dir);
+        }
+        for dir in Dir8::ALL {
+            assert_eq!(dir.opposite().offset(), -dir.offset());
+        }
+    }
+
+    #[test]
+    fn moves() {
+        let bounds = Bounds { cols: 3, rows: 2 };
+        let corner = Pos::new(0, 0);
+        assert_eq!(corner.checked_step(Dir4::N, bounds), None);
+        assert_eq!(corner.checked_step(Dir8::SE, bounds), Some(Pos::new(1, 1)));
+        assert_eq!(corner.wrapping_step(Dir4::W, bounds), Pos::new(2, 0));
+        assert_eq!(corner.wrapping_step(Dir8::NW, bounds), Pos::new(2, 1));
+        assert_eq!(corner.neighbours(Dir8::ALL, bounds).count(), 3);
+        assert_eq!(Pos::new(1, 0).neighbours(Dir4::ALL, bounds).count(), 3);
+        assert_eq!(Pos::from_index(5, 3), Pos::new(2, 1));
+        assert_eq!(Pos::new(2, 1).index(3), 5);
+        assert_eq!(Pos::new(2, 1) - Pos::new(1, 1), Pos::new(1, 0));
+        let point = Point::from(Pos::new(2, 1)) - Point { x: 3, y: 0 };
+        assert_eq!(point, Point::new(-1, 1));
+        assert!(!bounds.contains(point));
+    }
+}
@@ -0,0 +1,112 @@
+//! Cycle detection for iterated state machines, `x, f(x), f(f(x)), ...`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Shape of a state sequence: `prefix` states before the first repeating
+/// state, then a loop of `length` states
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Cycle {
+    pub(crate) prefix: usize,
+    pub(crate) length: usize,
+}
+
+impl Cycle {
+    /// Earliest iteration with the same state as iteration `n`
+    pub(crate) fn reduce(&self, n: usize) -> usize {
+        if n < self.prefix {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.length
+        }
+    }
+
+    /// State after `n` iterations, stepping at most `prefix + length` times
+    pub(crate) fn fast_forward<T>(&self, start: T, mut step: impl FnMut(&T) -> T, n: usize) -> T {
+        (0..self.reduce(n)).fold(start, |state, _| step(&state))
+    }
+}
+
+/// Brent's algorithm, only two states are kept at any time
+pub(crate) fn brent<T: Clone + Eq>(start: T, mut step: impl FnMut(&T) -> T) -> Cycle {
+    let (mut power, mut length) = (1, 1);
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    // Hare `length` ahead, they meet at the first state of the loop
+    let mut tortoise = start.clone();
+    let mut hare = (0..length).fold(start, |state, _| step(&state));
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    Cycle { prefix, length }
+}
+
+/// Cycle of the sequence and its first `prefix + length` states, found by
+/// remembering every state so each is computed only once
+pub(crate) fn history<T: Clone + Eq + Hash>(
+    start: T,
+    mut step: impl FnMut(&T) -> T,
+) -> (Cycle, Vec<T>) {
+    let mut seen = HashMap::new();
+    let mut states = Vec::new();
+    let mut state = start;
+    loop {
+        if let Some(&prefix) = seen.get(&state) {
+            let length = states.len() - prefix;
+            return (Cycle { prefix, length }, states);
+        }
+        seen.insert(state.clone(), states.len());
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn methods() {
+        // 0 -> 1 -> ... -> 4 -> 5 -> ... -> 11 -> 5
+        let step = |&x: &u32| if x == 11 { 5 } else { x + 1 };
+        let expected = Cycle {
+            prefix: 5,
+            length: 7,
+        };
+        assert_eq!(brent(0, step), expected);
+        assert_eq!(history(0, step).0, expected);
+        assert_eq!(
+            brent(3, |_| 3),
+            Cycle {
+                prefix: 0,
+                length: 1
+            }
+        );
+
+        let (cycle, states) = history(0, step);
+        assert_eq!(states.len(), 12);
+        assert_eq!(
+            states[cycle.reduce(1_000_000_000)],
+            5 + (1_000_000_000 - 5) % 7
+        );
+        assert_eq!(
+            cycle.fast_forward(0, step, 1_000_000_000),
+            5 + (1_000_000_000 - 5) % 7
+        );
+        assert_eq!(cycle.fast_forward(0, step, 3), 3);
+    }
+}
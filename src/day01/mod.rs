@@ -1,92 +1,288 @@
-#![cfg(test)]
-use std::fs;
+use std::ops::Range;
 
-#[test]
-fn task1() {
-    // let path = "src/day01/sample.txt";
-    let path = "src/day01/input.txt";
-    let content = fs::read_to_string(path).unwrap();
-    // println!("[[FILE]]\n{content}");
+use crate::checked;
+use crate::matcher::Matcher;
+#[cfg(test)]
+use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Settings, Solution};
+
+pub(crate) struct Day01;
+
+impl Solution for Day01 {
+    fn day(&self) -> u8 {
+        1
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        match part {
+            1 => Ok(part1(input)?.into()),
+            2 => Ok(part2(input)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+
+    fn explain(&self, part: u8, input: &str, _settings: &Settings) -> Option<Res<String>> {
+        match part {
+            1 => Some(explain(input, &Matcher::new(vocabulary(&[])))),
+            2 => Some(explain(input, &Matcher::new(vocabulary(&WORDS)))),
+            _ => None,
+        }
+    }
+}
+
+const WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+/// The digits `1` to `9`, followed by `words` for the same values when given
+fn vocabulary<'a>(words: &[&'a str]) -> Vec<(&'a str, u32)> {
+    const DIGITS: [&str; 9] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+    DIGITS
+        .iter()
+        .chain(words)
+        .zip((1..=9).cycle())
+        .map(|(&word, value)| (word, value))
+        .collect()
+}
+
+/// A digit, or a word for one, at `span` of its line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    span: Range<usize>,
+    value: u32,
+}
+
+/// How the calibration value of a line came about
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineReport<'a> {
+    /// Line number, starting at 1
+    number: usize,
+    line: &'a str,
+    /// Every token found, overlapping ones included, by start
+    tokens: Vec<Token>,
+    first: u32,
+    last: u32,
+}
 
-    let result: u32 = content
+impl LineReport<'_> {
+    fn value(&self) -> u32 {
+        self.first * 10 + self.last
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CalibrationError {
+    NoDigit { number: usize, line: String },
+}
+
+impl std::fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoDigit { number, line } => write!(f, "line {number} has no digit: '{line}'"),
+        }
+    }
+}
+
+impl From<CalibrationError> for String {
+    fn from(err: CalibrationError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Report of every line, the tokens found by `matcher` in a single pass
+fn report<'a>(
+    content: &'a str,
+    matcher: &Matcher<u32>,
+) -> Result<Vec<LineReport<'a>>, CalibrationError> {
+    content
         .lines()
-        .map(|line| {
-            let first = line
-                .chars()
-                .filter(|c| c.is_numeric())
-                .next()
-                .unwrap()
-                .to_digit(10)
-                .unwrap();
-            let last = line
-                .chars()
-                .filter(|c| c.is_numeric())
-                .last()
-                .unwrap()
-                .to_digit(10)
-                .unwrap();
-            first * 10 + last
+        .enumerate()
+        .map(|(idx, line)| {
+            // println!("# {line}");
+
+            let mut tokens = matcher
+                .find_iter(line)
+                .map(|found| Token {
+                    span: found.start..found.end,
+                    value: *found.value,
+                })
+                .collect::<Vec<_>>();
+            tokens.sort_by_key(|token| token.span.start);
+
+            // Overlapping words like "twone" count for both ends
+            let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+                return Err(CalibrationError::NoDigit {
+                    number: idx + 1,
+                    line: line.to_string(),
+                });
+            };
+            Ok(LineReport {
+                number: idx + 1,
+                line,
+                first: first.value,
+                last: last.value,
+                tokens,
+            })
         })
-        .sum();
+        .collect()
+}
 
+/// Sum of the first and last digit of every line, as found by `matcher`
+fn calibrate(content: &str, matcher: &Matcher<u32>) -> Res<u32> {
+    let reports = report(content, matcher)?;
+    checked::sum(
+        reports.iter().map(|line| Ok(line.value())),
+        "calibration sum",
+    )
+}
+
+/// The reports as an aligned table, with the sum below
+fn explain(content: &str, matcher: &Matcher<u32>) -> Res<String> {
+    let reports = report(content, matcher)?;
+    let header = ["line", "text", "tokens", "first", "last", "value"].map(String::from);
+    let rows = reports
+        .iter()
+        .map(|report| {
+            let tokens = report
+                .tokens
+                .iter()
+                .map(|token| {
+                    let span = &token.span;
+                    format!(
+                        "{}@{}..{}",
+                        &report.line[span.clone()],
+                        span.start,
+                        span.end
+                    )
+                })
+                .collect::<Vec<_>>();
+            [
+                report.number.to_string(),
+                report.line.to_string(),
+                tokens.join(" "),
+                report.first.to_string(),
+                report.last.to_string(),
+                report.value().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut widths = header.clone().map(|title| title.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(col, (cell, width))| match col {
+                // Text columns to the left, numbers to the right
+                1 | 2 => format!("{cell:width$}"),
+                _ => format!("{cell:>width$}"),
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        out += line.trim_end();
+        out.push('\n');
+    }
+    out += &format!("sum {}\n", calibrate(content, matcher)?);
+    Ok(out)
+}
+
+fn part1(content: &str) -> Res<u32> {
+    calibrate(content, &Matcher::new(vocabulary(&[])))
+}
+
+fn part2(content: &str) -> Res<u32> {
+    calibrate(content, &Matcher::new(vocabulary(&WORDS)))
+}
+
+#[test]
+fn task1() {
+    let text = open_first(&[
+        "src/day01/input.txt",  //
+        "src/day01/sample.txt", //
+    ])
+    .unwrap();
+    // println!("[[FILE]]\n{text}");
+
+    let result = part1(&text).unwrap();
     println!("Result: {result}");
 }
 
 #[test]
 fn task2() {
-    // let path = "src/day01/sample2.txt";
-    let path = "src/day01/input.txt";
-    let content = fs::read_to_string(path).unwrap();
+    let text = open_first(&[
+        "src/day01/input.txt",   //
+        "src/day01/sample2.txt", //
+    ])
+    .unwrap();
 
-    let digits = [
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
+    let result = part2(&text).unwrap();
+    println!("Result: {result}");
+}
 
-    let next_digit = |line: &str| {
-        // Forwards search
-        line.chars().enumerate().find_map(|(i, c)| {
-            if c.is_numeric() {
-                c.to_digit(10)
-            } else {
-                let text = &line[i..];
-                // println!("-> {text}");
-                digits
-                    .iter()
-                    .enumerate()
-                    .find_map(|(idx, num)| text.starts_with(num).then_some((idx + 1) as u32))
-            }
-        })
-    };
-    let last_digit = |line: &str| {
-        // Reverse search
-        line.chars().rev().enumerate().find_map(|(i, c)| {
-            if c.is_numeric() {
-                c.to_digit(10)
-            } else {
-                let i = line.len() - i;
-                let text = &line[0..i];
-                // println!("<- {text}");
-                digits
-                    .iter()
-                    .enumerate()
-                    .find_map(|(idx, num)| text.ends_with(num).then_some((idx + 1) as u32))
-            }
-        })
-    };
+#[test]
+fn vocabularies() {
+    let english = Matcher::new(vocabulary(&WORDS));
+    assert_eq!(calibrate("twone", &english), Ok(21));
+    assert_eq!(
+        calibrate("eightwothree\n7pqrstsixteen", &english),
+        Ok(83 + 76)
+    );
+    assert!(calibrate("abc", &english).is_err());
 
-    let result: u32 = content
-        .lines()
-        .map(|line| {
-            // println!("# {line}");
+    let german = [
+        "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+    ];
+    let german = Matcher::new(vocabulary(&german));
+    assert_eq!(calibrate("xfünfzweiachtx", &german), Ok(58));
 
-            let first = next_digit(line).unwrap();
-            let last = last_digit(line).unwrap();
-            let value = first * 10 + last;
+    // "zero" on top of the usual words
+    let zero = Matcher::new(
+        vocabulary(&WORDS)
+            .into_iter()
+            .chain([("zero", 0), ("0", 0)]),
+    );
+    assert_eq!(calibrate("zeronetwo0", &zero), Ok(0));
+    assert_eq!(calibrate("onezero", &zero), Ok(10));
+}
 
-            // println!("! {value}");
-            return value;
-        })
-        .sum();
+#[test]
+fn diagnostics() {
+    let english = Matcher::new(vocabulary(&WORDS));
+    let reports = report("eighthree\n4nineeightseven2", &english).unwrap();
+    assert_eq!(reports[0].tokens.len(), 2);
+    assert_eq!(
+        reports[0].tokens[1],
+        Token {
+            span: 4..9,
+            value: 3
+        }
+    );
+    assert_eq!(reports[0].value(), 83);
+    assert_eq!((reports[1].number, reports[1].value()), (2, 42));
 
-    println!("Result: {result}");
+    let err = report("1abc2\nabc", &english).unwrap_err();
+    assert_eq!(
+        err,
+        CalibrationError::NoDigit {
+            number: 2,
+            line: "abc".to_string()
+        }
+    );
+    assert_eq!(err.to_string(), "line 2 has no digit: 'abc'");
+
+    let table = explain("eighthree\n7", &english).unwrap();
+    let expected = "\
+line  text       tokens                 first  last  value
+   1  eighthree  eight@0..5 three@4..9      8     3     83
+   2  7          7@0..1                     7     7     77
+sum 160
+";
+    assert_eq!(table, expected);
 }
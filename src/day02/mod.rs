@@ -1,12 +1,38 @@
 #![allow(unused)]
-#![cfg(test)]
-use std::fs;
 
-#[derive(Debug, Default, Clone, Copy)]
+use std::collections::BTreeMap;
+
+use crate::checked;
+#[cfg(test)]
+use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Settings, Solution};
+
+/// Colour names interned to compact indices, in order of first appearance
+#[derive(Debug, Default, Clone)]
+struct Palette {
+    names: Vec<String>,
+}
+
+impl Palette {
+    fn intern(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|known| known == name) {
+            Some(idx) => idx,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+}
+
+/// Cube count per colour index, colours past the end have none
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 struct CubeSet {
-    red: u32,
-    green: u32,
-    blue: u32,
+    counts: Vec<u32>,
 }
 
 #[derive(Debug, Default)]
@@ -15,10 +41,21 @@ struct Game {
     sets: Vec<CubeSet>,
 }
 
-type Res<T> = Result<T, String>;
+/// Games sharing the palette of their colours
+#[derive(Debug, Default)]
+struct Record {
+    palette: Palette,
+    games: Vec<Game>,
+}
+
+/// `bag` holds the bag limits, `total` the cube count of the bag to estimate
+const SETTINGS: &[&str] = &["bag", "total"];
+
+/// The bag of the puzzle, used when `day02.bag` is not set
+const DEFAULT_BAG: &str = "red=12, green=13, blue=14";
 
 impl CubeSet {
-    fn parse(text: &str) -> Res<Self> {
+    fn parse(text: &str, palette: &mut Palette) -> Res<Self> {
         let mut set = CubeSet::default();
 
         for cube in text.split(',') {
@@ -27,41 +64,66 @@ impl CubeSet {
                 .split_once(' ')
                 .ok_or_else(|| "Unable to split color and count".to_string())?;
             let count = count.parse::<u32>().map_err(|e| format!("{e:?}"))?;
-            match color {
-                "red" => {
-                    set.red = count;
-                }
-                "green" => {
-                    set.green = count;
-                }
-                "blue" => {
-                    set.blue = count;
-                }
-                _ => {
-                    return Err(format!("Invalid color: '{color}'"));
-                }
-            }
+            set.set(palette.intern(color.trim()), count);
         }
 
         Ok(set)
     }
 
+    /// A bag like `red=12, green=13`, any colour it leaves out has no cubes
+    fn parse_bag(text: &str, palette: &mut Palette) -> Res<Self> {
+        let mut set = CubeSet::default();
+        for cube in text.split(',').filter(|cube| !cube.trim().is_empty()) {
+            let (color, count) = cube
+                .split_once('=')
+                .ok_or_else(|| format!("expected color=count in bag, got '{}'", cube.trim()))?;
+            let count = count
+                .trim()
+                .parse::<u32>()
+                .map_err(|e| format!("invalid count of {}: {e}", color.trim()))?;
+            set.set(palette.intern(color.trim()), count);
+        }
+        Ok(set)
+    }
+
+    fn get(&self, color: usize) -> u32 {
+        self.counts.get(color).copied().unwrap_or(0)
+    }
+
+    fn set(&mut self, color: usize, count: u32) {
+        if self.counts.len() <= color {
+            self.counts.resize(color + 1, 0);
+        }
+        self.counts[color] = count;
+    }
+
+    /// Like `12 red, 13 green`, every colour of `palette` included
+    fn describe(&self, palette: &Palette) -> String {
+        palette
+            .names
+            .iter()
+            .enumerate()
+            .map(|(color, name)| format!("{} {name}", self.get(color)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     fn bigger_than(&self, other: &Self) -> bool {
-        self.red > other.red || self.blue > other.blue || self.green > other.green
+        (0..self.counts.len()).any(|color| self.get(color) > other.get(color))
     }
 
     fn max(&self, other: &Self) -> Self {
-        let f = std::cmp::max;
+        let colors = self.counts.len().max(other.counts.len());
         Self {
-            red: f(self.red, other.red),
-            green: f(self.green, other.green),
-            blue: f(self.blue, other.blue),
+            counts: (0..colors)
+                .map(|color| std::cmp::max(self.get(color), other.get(color)))
+                .collect(),
         }
     }
 }
 
 impl Game {
-    fn parse(line: &str) -> Res<Self> {
+    fn parse(line: &str, palette: &mut Palette) -> Res<Self> {
         let (name, sets) = line
             .split_once(':')
             .ok_or_else(|| "Unable to split indentifier from cube sets".to_string())?;
@@ -71,59 +133,311 @@ impl Game {
             .ok_or_else(|| "Unable to split id".to_string())?
             .parse::<u32>()
             .map_err(|e| format!("{e:?}"))?;
-        let sets = sets.split(';').map(CubeSet::parse).collect::<Res<_>>()?;
+        let sets = sets
+            .split(';')
+            .map(|set| CubeSet::parse(set, palette))
+            .collect::<Res<_>>()?;
         Ok(Game { id, sets })
     }
+
+    /// Whether every draw fits in `bag`
+    fn possible(&self, bag: &CubeSet) -> bool {
+        !self.sets.iter().any(|set| set.bigger_than(bag))
+    }
 }
 
-fn parse(file: &str) -> Result<Vec<Game>, String> {
-    fs::read_to_string(file)
-        .map_err(|e| format!("{e:?}"))?
+fn parse(text: &str) -> Result<Record, String> {
+    let mut palette = Palette::default();
+    let games = text
         .lines()
-        .map(Game::parse)
+        .map(|line| Game::parse(line, &mut palette))
+        .collect::<Res<_>>()?;
+    Ok(Record { palette, games })
+}
+
+/// Ids of the games possible with each of `bags`
+fn feasible(data: &Record, bags: &[CubeSet]) -> Vec<Vec<u32>> {
+    bags.iter()
+        .map(|bag| {
+            data.games
+                .iter()
+                .filter(|game| game.possible(bag))
+                .map(|game| game.id)
+                .collect()
+        })
         .collect()
 }
 
-#[test]
-fn task1() {
-    // let path = "src/day02/sample.txt";
-    let path = "src/day02/input.txt";
-    let data = parse(path).unwrap();
-    // println!("[[DATA]] {data:#?}");
+/// Smallest bag that makes every game possible
+fn smallest_bag(data: &Record) -> CubeSet {
+    data.games
+        .iter()
+        .flat_map(|game| &game.sets)
+        .fold(CubeSet::default(), |bag, set| bag.max(set))
+}
 
-    let max = CubeSet {
-        red: 12,
-        green: 13,
-        blue: 14,
+/// Per colour, the ids of the games drawing as many cubes as the smallest bag holds
+fn limiting(data: &Record) -> Vec<Vec<u32>> {
+    let bag = smallest_bag(data);
+    (0..data.palette.len())
+        .map(|color| {
+            data.games
+                .iter()
+                .filter(|game| {
+                    let most = game.sets.iter().map(|set| set.get(color)).max();
+                    most.is_some_and(|most| most > 0 && most == bag.get(color))
+                })
+                .map(|game| game.id)
+                .collect()
+        })
+        .collect()
+}
+
+/// Per colour, how many draws showed each count of it, zero included
+fn distribution(data: &Record) -> Vec<BTreeMap<u32, usize>> {
+    let mut out = vec![BTreeMap::new(); data.palette.len()];
+    for set in data.games.iter().flat_map(|game| &game.sets) {
+        for (color, counts) in out.iter_mut().enumerate() {
+            *counts.entry(set.get(color)).or_insert(0) += 1;
+        }
+    }
+    out
+}
+
+/// Most likely bag of `total` cubes, every draw taken without replacement
+///
+/// A draw of `x` cubes of each colour from a bag of `c` has a likelihood
+/// proportional to the product of `C(c, x)`, so the log-likelihood splits
+/// into a concave function per colour. Handing out the cubes one at a time
+/// to the colour gaining most, starting from the smallest bag, maximises it.
+fn estimate(data: &Record, total: u32) -> Res<CubeSet> {
+    let mut bag = smallest_bag(data);
+    let least = bag.counts.iter().sum::<u32>();
+    if total < least {
+        return Err(format!(
+            "{total} cubes are fewer than the {least} the games need"
+        ));
+    }
+    let draws = distribution(data);
+    // Log-likelihood gained by a colour holding `c + 1` cubes instead of `c`
+    let gain = |color: usize, c: u32| -> f64 {
+        draws[color]
+            .iter()
+            .map(|(&x, &times)| times as f64 * ((c + 1) as f64 / (c + 1 - x) as f64).ln())
+            .sum()
     };
+    for _ in least..total {
+        let best = (0..data.palette.len())
+            .map(|color| (color, gain(color, bag.get(color))))
+            .fold(
+                None,
+                |best: Option<(usize, f64)>, (color, gain)| match best {
+                    Some((_, most)) if most >= gain => best,
+                    _ => Some((color, gain)),
+                },
+            )
+            .ok_or_else(|| "no colours to estimate".to_string())?;
+        bag.set(best.0, bag.get(best.0) + 1);
+    }
+    Ok(bag)
+}
 
-    let result: u32 = data
-        .iter()
-        .filter(|game| game.sets.iter().find(|set| set.bigger_than(&max)).is_none())
-        .map(|game| game.id)
-        .sum();
+/// The statistics above as text, with an estimate when `total` is given
+fn analysis(data: &Record, total: Option<u32>) -> Res<String> {
+    let mut out = format!(
+        "smallest bag: {}\n",
+        smallest_bag(data).describe(&data.palette)
+    );
+    let names = &data.palette.names;
+    for (name, ids) in names.iter().zip(limiting(data)) {
+        let ids = ids.iter().map(u32::to_string).collect::<Vec<_>>();
+        out += &format!("{name} limited by game(s) {}\n", ids.join(", "));
+    }
+    for (name, counts) in names.iter().zip(distribution(data)) {
+        let counts = counts
+            .iter()
+            .map(|(count, times)| format!("{count}x{times}"))
+            .collect::<Vec<_>>();
+        out += &format!("{name} draws: {}\n", counts.join(" "));
+    }
+    if let Some(total) = total {
+        let bag = estimate(data, total)?;
+        out += &format!("likely bag of {total}: {}\n", bag.describe(&data.palette));
+    }
+    Ok(out)
+}
+
+pub(crate) struct Day02;
+
+impl Solution for Day02 {
+    fn day(&self) -> u8 {
+        2
+    }
+
+    fn settings(&self) -> &'static [&'static str] {
+        SETTINGS
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        self.solve_with(part, input, &Settings::default())
+    }
+
+    fn solve_with(&self, part: u8, input: &str, settings: &Settings) -> Res<Answer> {
+        settings.check(self.day(), SETTINGS)?;
+        let mut data = parse(input)?;
+        match part {
+            1 => {
+                let bag = settings.get("bag").unwrap_or(DEFAULT_BAG);
+                let bag = CubeSet::parse_bag(bag, &mut data.palette)?;
+                Ok(part1(&data, &bag)?.into())
+            }
+            2 => Ok(part2(&data)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+
+    fn explain(&self, _part: u8, input: &str, settings: &Settings) -> Option<Res<String>> {
+        let report = || {
+            settings.check(self.day(), SETTINGS)?;
+            let total = settings
+                .get("total")
+                .map(|total| {
+                    total
+                        .parse::<u32>()
+                        .map_err(|e| format!("invalid day02.total '{total}': {e}"))
+                })
+                .transpose()?;
+            analysis(&parse(input)?, total)
+        };
+        Some(report())
+    }
+}
 
+fn part1(data: &Record, bag: &CubeSet) -> Res<u32> {
+    let ids = feasible(data, std::slice::from_ref(bag)).remove(0);
+    checked::sum(ids.into_iter().map(Ok), "sum of ids")
+}
+
+fn part2(data: &Record) -> Res<u32> {
+    let powers = data.games.iter().map(|game| {
+        let mut iter = game.sets.iter();
+        let init = iter.next().unwrap().clone();
+        let set = iter.fold(init, |a, b| a.max(b));
+        // A colour the game never shows needs no cubes, the power is then 0
+        let counts = (0..data.palette.len()).map(|color| Ok(set.get(color)));
+        let power = checked::product(counts, "power")?;
+        // println!("[{}]: {:?} => {}", game.id, set, power);
+        Ok(power)
+    });
+    checked::sum(powers, "sum of powers")
+}
+
+#[test]
+fn task1() {
+    let text = open_first(&[
+        "src/day02/input.txt",  //
+        "src/day02/sample.txt", //
+    ])
+    .unwrap();
+    let mut data = parse(&text).unwrap();
+    // println!("[[DATA]] {data:#?}");
+
+    let bag = CubeSet::parse_bag(DEFAULT_BAG, &mut data.palette).unwrap();
+    let result = part1(&data, &bag).unwrap();
     println!("Result: {result}");
 }
 
 #[test]
 fn task2() {
-    // let path = "src/day02/sample.txt";
-    let path = "src/day02/input.txt";
-    let data = parse(path).unwrap();
+    let text = open_first(&[
+        "src/day02/input.txt",  //
+        "src/day02/sample.txt", //
+    ])
+    .unwrap();
+    let data = parse(&text).unwrap();
     // println!("[[DATA]] {data:#?}");
 
-    let result: u32 = data
-        .iter()
-        .map(|game| {
-            let mut iter = game.sets.iter();
-            let init = iter.next().unwrap().clone();
-            let set = iter.fold(init, |a, b| a.max(b));
-            let power = set.red * set.green * set.blue;
-            // println!("[{}]: {:?} => {}", game.id, set, power);
-            return power;
+    let result = part2(&data).unwrap();
+    println!("Result: {result}");
+}
+
+#[test]
+fn bags() {
+    let text = open_first(&["src/day02/sample.txt"]).unwrap();
+    let mut data = parse(&text).unwrap();
+    let candidates = [DEFAULT_BAG, "red=20, green=20, blue=20", "blue=6, green=3"]
+        .map(|bag| CubeSet::parse_bag(bag, &mut data.palette).unwrap());
+    assert_eq!(
+        feasible(&data, &candidates),
+        [vec![1, 2, 5], vec![1, 2, 3, 4, 5], vec![]]
+    );
+
+    // Colours beyond red, green and blue
+    let mut data = parse("Game 7: 3 teal, 1 red; 2 teal\nGame 8: 5 teal").unwrap();
+    assert_eq!(data.palette.names, ["teal", "red"]);
+    let bag = CubeSet::parse_bag("teal=4, red=1", &mut data.palette).unwrap();
+    assert_eq!(part1(&data, &bag), Ok(7));
+    assert_eq!(part2(&data), Ok(3));
+
+    let settings = Settings::from_iter([("bag", "red=20, green=20, blue=20")]);
+    let answer = Day02.solve_with(1, &text, &settings).unwrap();
+    assert_eq!(answer.to_string(), "15");
+    assert!(Day02
+        .solve_with(1, &text, &Settings::from_iter([("bags", "")]))
+        .is_err());
+    assert!(CubeSet::parse_bag("red:12", &mut data.palette).is_err());
+}
+
+#[test]
+fn statistics() {
+    let text = open_first(&["src/day02/sample.txt"]).unwrap();
+    let data = parse(&text).unwrap();
+    // Colours in order of first appearance
+    assert_eq!(data.palette.names, ["blue", "red", "green"]);
+    assert_eq!(
+        smallest_bag(&data).describe(&data.palette),
+        "15 blue, 20 red, 13 green"
+    );
+    assert_eq!(limiting(&data), [vec![4], vec![3], vec![3]]);
+    let red = &distribution(&data)[1];
+    assert_eq!(red.values().sum::<usize>(), 14);
+    assert_eq!(
+        red.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
+        [(0, 3), (1, 4), (3, 1), (4, 2), (6, 2), (14, 1), (20, 1)]
+    );
+
+    assert_eq!(estimate(&data, 48), Ok(smallest_bag(&data)));
+    assert!(estimate(&data, 47).is_err());
+
+    // The greedy estimate matches the best of every split of a two colour bag
+    let data = parse("Game 1: 2 red, 1 blue; 1 red; 3 red, 1 blue").unwrap();
+    let loglik = |bag: &CubeSet| -> f64 {
+        let choose = |n: u32, k: u32| {
+            (0..k)
+                .map(|i| ((n - i) as f64 / (i + 1) as f64).ln())
+                .sum::<f64>()
+        };
+        data.games[0]
+            .sets
+            .iter()
+            .map(|set| {
+                (0..2)
+                    .map(|color| choose(bag.get(color), set.get(color)))
+                    .sum::<f64>()
+            })
+            .sum()
+    };
+    let guess = estimate(&data, 10).unwrap();
+    let best = (3..=9)
+        .map(|red| CubeSet {
+            counts: vec![red, 10 - red],
         })
-        .sum();
+        .max_by(|a, b| loglik(a).total_cmp(&loglik(b)))
+        .unwrap();
+    assert_eq!(guess, best);
 
-    println!("Result: {result}");
+    let report = Day02.explain(1, &text, &Settings::from_iter([("total", "60")]));
+    let report = report.unwrap().unwrap();
+    assert!(report.contains("red limited by game(s) 3\n"));
+    assert!(report.contains("likely bag of 60: "));
 }
@@ -1,160 +1,609 @@
-#![cfg(test)]
-use std::{fs, ptr};
+use std::collections::HashMap;
+use std::ops::Range;
 
-#[test]
-fn task1() {
-    // let path = "src/day03/sample.txt";
-    let path = "src/day03/input.txt";
-    let data = fs::read_to_string(path).unwrap();
-    // println!("[[DATA]] {data:#?}");
+use crate::checked;
+use crate::coord::{Bounds, Dir8, Pos};
+#[cfg(test)]
+use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Settings, Solution};
 
-    let rows: Vec<&str> = data.lines().collect();
-    let r = rows.len() - 1;
-    let l = rows.get(0).unwrap_or(&".").len() - 1;
+/// A number of the schematic, on `row` over the columns `span`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PartNumber {
+    value: u32,
+    row: usize,
+    span: Range<usize>,
+}
 
-    let has_symbol = |text: &str| text.find(|c: char| c != '.' && !c.is_numeric()).is_some();
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Symbol {
+    ch: char,
+    row: usize,
+    col: usize,
+}
 
-    let result: u32 = rows
-        .iter()
-        .enumerate()
-        .map(|(row, line)| -> u32 {
-            // println!("[..] '{}'", line);
-            let mut sum = 0;
-            let mut e = 0;
-            while let Some(i) = line[e..]
-                .chars()
-                .position(|c| c.is_numeric())
-                .map(|i| e + i)
-            {
-                e = line[i..]
-                    .chars()
-                    .position(|c| !c.is_numeric())
-                    .map(|e| i + e)
-                    .unwrap_or(line.len());
-
-                // println!("[{}..{}]: '{}'", i, e, &line[i..e]);
-
-                // Column indices of neighborhood
-                let ri = if i > 0 { i - 1 } else { i };
-                let re = if e < l { e + 1 } else { e };
-
-                // Neighborhood checks
-                let r1 = row > 0 && has_symbol(&rows[row - 1][ri..re]);
-                let r2 = has_symbol(&line[ri..re]);
-                let r3 = row < r && has_symbol(&rows[row + 1][ri..re]);
-
-                // Require one adjecent symbol
-                if r1 || r2 || r3 {
-                    sum += line[i..e].parse::<u32>().unwrap();
+/// The numbers and symbols of a schematic, and which of them touch
+#[derive(Debug, Default)]
+struct Schematic {
+    numbers: Vec<PartNumber>,
+    symbols: Vec<Symbol>,
+    /// Indices of the numbers next to each symbol, in order
+    adjacent: Vec<Vec<usize>>,
+    /// Indices of the symbols next to each number, in order
+    touching: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    /// Lines may differ in length, anything but digits and `.` is a symbol
+    fn parse(text: &str) -> Res<Self> {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        // Number index of every digit cell
+        let mut cells = HashMap::new();
+        let mut cols = 0;
+        for (row, line) in text.lines().enumerate() {
+            let chars = line.chars().collect::<Vec<_>>();
+            cols = cols.max(chars.len());
+            let mut col = 0;
+            while col < chars.len() {
+                let ch = chars[col];
+                if !ch.is_ascii_digit() {
+                    if ch != '.' {
+                        symbols.push(Symbol { ch, row, col });
+                    }
+                    col += 1;
+                    continue;
+                }
+                let end = chars[col..]
+                    .iter()
+                    .position(|c| !c.is_ascii_digit())
+                    .map_or(chars.len(), |len| col + len);
+                let digits = chars[col..end].iter().collect::<String>();
+                let value = digits
+                    .parse::<u32>()
+                    .map_err(|e| format!("line {}: '{digits}': {e}", row + 1))?;
+                for x in col..end {
+                    cells.insert(Pos::new(x, row), numbers.len());
                 }
+                numbers.push(PartNumber {
+                    value,
+                    row,
+                    span: col..end,
+                });
+                col = end;
             }
-            return sum;
+        }
+
+        let bounds = Bounds {
+            cols,
+            rows: text.lines().count(),
+        };
+        let mut touching = vec![Vec::new(); numbers.len()];
+        let adjacent = symbols
+            .iter()
+            .enumerate()
+            .map(|(idx, symbol)| {
+                let mut near = Pos::new(symbol.col, symbol.row)
+                    .neighbours(Dir8::ALL, bounds)
+                    .filter_map(|pos| cells.get(&pos).copied())
+                    .collect::<Vec<_>>();
+                near.sort_unstable();
+                near.dedup();
+                for &number in &near {
+                    touching[number].push(idx);
+                }
+                near
+            })
+            .collect();
+
+        Ok(Self {
+            numbers,
+            symbols,
+            adjacent,
+            touching,
         })
-        .sum();
+    }
+
+    /// Numbers next to at least one symbol
+    fn parts(&self) -> impl Iterator<Item = &PartNumber> {
+        self.numbers
+            .iter()
+            .zip(&self.touching)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+}
+
+/// Which adjacent numbers of a symbol a query keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Count {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+/// How a query combines the numbers next to a symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Agg {
+    Sum,
+    Product,
+    Min,
+    Max,
+}
+
+/// Total over the `symbol`s with a `count` of adjacent numbers, each
+/// symbol's numbers combined with `agg`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Query {
+    symbol: char,
+    count: Count,
+    agg: Agg,
+}
+
+/// Settings of the part 2 query
+const SETTINGS: &[&str] = &["symbol", "count", "agg"];
+
+impl std::str::FromStr for Count {
+    type Err = String;
+
+    /// `3` for exactly three, `3+` for at least three
+    fn from_str(text: &str) -> Res<Self> {
+        let parse = |n: &str| {
+            n.parse::<usize>()
+                .map_err(|e| format!("invalid count '{text}': {e}"))
+        };
+        match text.strip_suffix('+') {
+            Some(n) => Ok(Self::AtLeast(parse(n)?)),
+            None => Ok(Self::Exactly(parse(text)?)),
+        }
+    }
+}
+
+impl std::str::FromStr for Agg {
+    type Err = String;
+
+    fn from_str(text: &str) -> Res<Self> {
+        match text {
+            "sum" => Ok(Self::Sum),
+            "product" => Ok(Self::Product),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            _ => Err(format!(
+                "invalid agg '{text}', expected sum, product, min or max"
+            )),
+        }
+    }
+}
+
+impl Count {
+    fn accepts(self, n: usize) -> bool {
+        match self {
+            Self::Exactly(count) => n == count,
+            Self::AtLeast(count) => n >= count,
+        }
+    }
+}
+
+impl Agg {
+    fn apply(self, values: impl IntoIterator<Item = u32>) -> Res<u32> {
+        let values = values.into_iter().map(Ok);
+        match self {
+            Self::Sum => checked::sum(values, "sum of adjacent numbers"),
+            Self::Product => checked::product(values, "product of adjacent numbers"),
+            Self::Min => values.min().unwrap_or(Ok(0)),
+            Self::Max => values.max().unwrap_or(Ok(0)),
+        }
+    }
+}
+
+impl Default for Query {
+    /// The gears of part 2
+    fn default() -> Self {
+        Self {
+            symbol: '*',
+            count: Count::Exactly(2),
+            agg: Agg::Product,
+        }
+    }
+}
+
+impl Query {
+    /// The default query with any of the `symbol`, `count` and `agg` settings applied
+    fn from_settings(settings: &Settings) -> Res<Self> {
+        settings.check(3, SETTINGS)?;
+        let mut query = Self::default();
+        if let Some(symbol) = settings.get("symbol") {
+            let mut chars = symbol.chars();
+            query.symbol = match (chars.next(), chars.next()) {
+                (Some(ch), None) => ch,
+                _ => return Err(format!("symbol '{symbol}' is not a single character")),
+            };
+        }
+        if let Some(count) = settings.get("count") {
+            query.count = count.parse()?;
+        }
+        if let Some(agg) = settings.get("agg") {
+            query.agg = agg.parse()?;
+        }
+        Ok(query)
+    }
+}
+
+impl Query {
+    /// Whether the query takes `symbol` with `near` adjacent numbers
+    fn selects(self, symbol: &Symbol, near: usize) -> bool {
+        symbol.ch == self.symbol && self.count.accepts(near)
+    }
+}
+
+impl Schematic {
+    /// The symbols a query selects, with their adjacent numbers
+    fn matches(&self, query: Query) -> impl Iterator<Item = (&Symbol, Vec<&PartNumber>)> {
+        self.symbols
+            .iter()
+            .zip(&self.adjacent)
+            .filter(move |(symbol, near)| query.selects(symbol, near.len()))
+            .map(|(symbol, near)| {
+                let near = near.iter().map(|&idx| &self.numbers[idx]).collect();
+                (symbol, near)
+            })
+    }
+
+    /// Total of the query over every symbol it selects, a number next to two
+    /// selected symbols counts for both
+    fn query(&self, query: Query) -> Res<u32> {
+        let values = self
+            .matches(query)
+            .map(|(_, near)| query.agg.apply(near.iter().map(|number| number.value)));
+        checked::sum(values, "query total")
+    }
+}
+
+/// How the renderer draws a run of cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Part,
+    Ignored,
+    Symbol,
+    Gear,
+}
+
+impl Style {
+    const ALL: [Self; 4] = [Self::Part, Self::Ignored, Self::Symbol, Self::Gear];
+
+    /// Green parts, dim ignored numbers, yellow symbols and bold red gears
+    fn ansi(self) -> &'static str {
+        match self {
+            Self::Part => "\x1b[32m",
+            Self::Ignored => "\x1b[2m",
+            Self::Symbol => "\x1b[33m",
+            Self::Gear => "\x1b[1;31m",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Part => "part",
+            Self::Ignored => "ignored",
+            Self::Symbol => "symbol",
+            Self::Gear => "gear",
+        }
+    }
+}
+
+/// A styled run of a line, over the columns `span`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mark {
+    span: Range<usize>,
+    style: Style,
+    /// Neighbours of the run, shown as a tooltip
+    title: String,
+}
+
+impl Schematic {
+    /// The styled runs of each row, by column, with gears as in part 2
+    fn marks(&self, rows: usize) -> Vec<Vec<Mark>> {
+        let gears = Query::default();
+        let at =
+            |symbol: &Symbol| format!("{} at {}:{}", symbol.ch, symbol.row + 1, symbol.col + 1);
+        let mut marks = vec![Vec::new(); rows];
+        for (number, near) in self.numbers.iter().zip(&self.touching) {
+            let (style, title) = match near[..] {
+                [] => (
+                    Style::Ignored,
+                    format!("{} touches no symbol", number.value),
+                ),
+                _ => {
+                    let near = near.iter().map(|&idx| at(&self.symbols[idx]));
+                    let near = near.collect::<Vec<_>>().join(", ");
+                    (Style::Part, format!("{} next to {near}", number.value))
+                }
+            };
+            marks[number.row].push(Mark {
+                span: number.span.clone(),
+                style,
+                title,
+            });
+        }
+        for (symbol, near) in self.symbols.iter().zip(&self.adjacent) {
+            let values = near.iter().map(|&idx| self.numbers[idx].value);
+            let mut title = format!(
+                "{} next to [{}]",
+                at(symbol),
+                values
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let style = if gears.selects(symbol, near.len()) {
+                let ratio = gears
+                    .agg
+                    .apply(near.iter().map(|&idx| self.numbers[idx].value));
+                title += &match ratio {
+                    Ok(ratio) => format!(", ratio {ratio}"),
+                    Err(err) => format!(", {err}"),
+                };
+                Style::Gear
+            } else {
+                Style::Symbol
+            };
+            marks[symbol.row].push(Mark {
+                span: symbol.col..symbol.col + 1,
+                style,
+                title,
+            });
+        }
+        for row in &mut marks {
+            row.sort_by_key(|mark| mark.span.start);
+        }
+        marks
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The lines of `text`, each mark wrapped by `wrap` and the cells between as they are
+fn render_lines(text: &str, marks: &[Vec<Mark>], wrap: impl Fn(&Mark, &str) -> String) -> String {
+    let mut out = String::new();
+    for (line, marks) in text.lines().zip(marks) {
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut col = 0;
+        for mark in marks {
+            out.extend(&chars[col..mark.span.start]);
+            let cells = chars[mark.span.clone()].iter().collect::<String>();
+            out += &wrap(mark, &cells);
+            col = mark.span.end;
+        }
+        out.extend(&chars[col..]);
+        out.push('\n');
+    }
+    out
+}
+
+/// The schematic with ANSI colours, followed by the legend and every gear ratio
+pub(crate) fn render(text: &str) -> Res<String> {
+    const RESET: &str = "\x1b[0m";
+    let marks = Schematic::parse(text)?.marks(text.lines().count());
+    let mut out = render_lines(text, &marks, |mark, cells| {
+        format!("{}{cells}{RESET}", mark.style.ansi())
+    });
+    let legend = Style::ALL.map(|style| format!("{}{}{RESET}", style.ansi(), style.name()));
+    out += &format!("\n{}\n", legend.join(" "));
+    for mark in marks
+        .iter()
+        .flatten()
+        .filter(|mark| mark.style == Style::Gear)
+    {
+        out += &format!("{}\n", mark.title);
+    }
+    Ok(out)
+}
+
+/// The schematic as a standalone HTML page, each run with its neighbours on hover
+pub(crate) fn render_html(text: &str) -> Res<String> {
+    let marks = Schematic::parse(text)?.marks(text.lines().count());
+    let body = render_lines(text, &marks, |mark, cells| {
+        format!(
+            "<span class=\"{}\" title=\"{}\">{}</span>",
+            mark.style.name(),
+            escape_html(&mark.title),
+            escape_html(cells)
+        )
+    });
+    Ok(format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Day 3 schematic</title>
+<style>
+body {{ background: #0f0f23; color: #555; }}
+.part {{ color: #3c3; }}
+.ignored {{ color: #777; text-decoration: line-through; }}
+.symbol {{ color: #ee3; }}
+.gear {{ color: #f44; font-weight: bold; }}
+span[title] {{ cursor: help; }}
+</style>
+</head>
+<body>
+<pre>
+{body}</pre>
+</body>
+</html>
+"
+    ))
+}
+
+pub(crate) struct Day03;
+
+impl Solution for Day03 {
+    fn day(&self) -> u8 {
+        3
+    }
+
+    fn settings(&self) -> &'static [&'static str] {
+        SETTINGS
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        self.solve_with(part, input, &Settings::default())
+    }
+
+    fn solve_with(&self, part: u8, input: &str, settings: &Settings) -> Res<Answer> {
+        let query = Query::from_settings(settings)?;
+        let schematic = Schematic::parse(input)?;
+        match part {
+            1 => Ok(part1(&schematic)?.into()),
+            2 => Ok(part2(&schematic, query)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+fn part1(schematic: &Schematic) -> Res<u32> {
+    let values = schematic.parts().map(|number| Ok(number.value));
+    checked::sum(values, "sum of parts")
+}
 
+/// The gear ratios by default, any other query when set
+fn part2(schematic: &Schematic, query: Query) -> Res<u32> {
+    schematic.query(query)
+}
+
+#[test]
+fn task1() {
+    let text = open_first(&[
+        "src/day03/input.txt",  //
+        "src/day03/sample.txt", //
+    ])
+    .unwrap();
+    // println!("[[DATA]] {text:#?}");
+
+    let schematic = Schematic::parse(&text).unwrap();
+    let result = part1(&schematic).unwrap();
     println!("Result: {result}");
 }
 
 #[test]
 fn task2() {
-    // let path = "src/day03/sample.txt";
-    let path = "src/day03/input.txt";
-    let data = fs::read_to_string(path).unwrap();
-    // println!("[[DATA]] {data:#?}");
+    let text = open_first(&[
+        "src/day03/input.txt",  //
+        "src/day03/sample.txt", //
+    ])
+    .unwrap();
+    // println!("[[DATA]] {text:#?}");
 
-    let rows: Vec<&str> = data.lines().collect();
-    let r = rows.len();
-    let l = rows.get(0).unwrap_or(&".").len();
+    let schematic = Schematic::parse(&text).unwrap();
+    let result = part2(&schematic, Query::default()).unwrap();
+    println!("Result: {result}");
+}
 
-    let subs = |i, m| if i > m { i - 1 } else { i - 0 };
-    let adds = |i, m| if i < m { i + 2 } else { i + 1 };
+#[test]
+fn edges() {
+    // Wider than high, with numbers against every edge
+    let schematic = Schematic::parse("12......7\n..*....#.\n3...45...").unwrap();
+    assert_eq!(
+        schematic.numbers[1],
+        PartNumber {
+            value: 7,
+            row: 0,
+            span: 8..9
+        }
+    );
+    assert_eq!(
+        schematic.symbols[1],
+        Symbol {
+            ch: '#',
+            row: 1,
+            col: 7
+        }
+    );
+    assert_eq!(schematic.adjacent, [vec![0], vec![1]]);
+    assert_eq!(part1(&schematic), Ok(19));
+    assert_eq!(part2(&schematic, Query::default()), Ok(0));
 
-    let result: u32 = rows
-        .iter()
-        .enumerate()
-        .map(|(row, line)| -> u32 {
-            let ri = subs(row, 0);
-            let re = adds(row, r);
-            line.chars()
-                .enumerate()
-                .filter_map(|(i, c)| (c == '*').then_some(i))
-                .map(|col| -> u32 {
-                    let ci = subs(col, 0);
-                    let ce = adds(col, l);
-
-                    let mut g1: Option<&str> = None;
-                    let mut g2: Option<&str> = None;
-
-                    // square: (ri,ci) -> (re,ce)
-                    for row in &rows[ri..re] {
-                        // println!("#[{}..{}] {}", ri, re, &row[ci..ce]);
-                        for (c, char) in row[ci..ce].char_indices() {
-                            if !char.is_numeric() {
-                                continue;
-                            }
-                            let ci = c + ci;
-                            let ce = 1 + ci;
-                            // println!("HIT: '{}'", &row[ci..ce]);
-                            // There is a number here
-                            let i = row[..ci]
-                                .chars()
-                                .rev()
-                                .position(|c| !c.is_numeric())
-                                .map(|p| ci - p)
-                                .unwrap_or(0);
-                            let e = row[ce..]
-                                .chars()
-                                .position(|c| !c.is_numeric())
-                                .map(|p| ce + p)
-                                .unwrap_or(r);
-
-                            let span = &row[i..e];
-                            // println!("[{}..{}] '{}'", i, e, span);
-
-                            // Maybe first gear
-                            let Some(that) = g1 else {
-                                g1.replace(span);
-                                continue;
-                            };
-                            // Maybe duplicate gear
-                            if ptr::eq(that, span) {
-                                continue;
-                            }
-
-                            // Maybe second gear
-                            let Some(that) = g2 else {
-                                g2.replace(span);
-                                continue;
-                            };
-                            // Maybe duplicate gear
-                            if ptr::eq(that, span) {
-                                continue;
-                            }
-
-                            // Unexpected third gear
-                            return 0;
-                        }
-                    }
+    // Ragged lines, and a gear shared by a number on two of its sides
+    let schematic = Schematic::parse("5\n*10\n2\n").unwrap();
+    assert_eq!(schematic.adjacent, [vec![0, 1, 2]]);
+    let schematic = Schematic::parse("5*\n.10").unwrap();
+    assert_eq!(part2(&schematic, Query::default()), Ok(50));
+}
 
-                    // Must have first gear
-                    let Some(g1) = g1 else {
-                        return 0;
-                    };
-
-                    // Must have second gear
-                    let Some(g2) = g2 else {
-                        return 0;
-                    };
-
-                    let g1 = g1.parse::<u32>().unwrap();
-                    let g2 = g2.parse::<u32>().unwrap();
-                    let ratio = g1 * g2;
-                    // println!("* {}", ratio);
-                    return ratio;
-                })
-                .sum()
-        })
-        .sum();
+#[test]
+fn queries() {
+    let text = open_first(&["src/day03/sample.txt"]).unwrap();
+    let schematic = Schematic::parse(&text).unwrap();
+    assert_eq!(
+        schematic.query(Query::default()),
+        part2(&schematic, Query::default())
+    );
 
-    println!("Result: {result}");
+    let settings = Settings::from_iter([("symbol", "*"), ("count", "1+"), ("agg", "max")]);
+    let query = Query::from_settings(&settings).unwrap();
+    assert_eq!(query.count, Count::AtLeast(1));
+    // The three stars touch 467 and 35, 617, and 755 and 598
+    assert_eq!(schematic.query(query), Ok(467 + 617 + 755));
+
+    let dollar = Query {
+        symbol: '$',
+        count: Count::AtLeast(1),
+        agg: Agg::Sum,
+    };
+    assert_eq!(schematic.query(dollar), Ok(664));
+    let lonely = Query {
+        count: Count::Exactly(1),
+        agg: Agg::Min,
+        ..Query::default()
+    };
+    assert_eq!(schematic.query(lonely), Ok(617));
+
+    let answer = Day03.solve_with(
+        2,
+        &text,
+        &Settings::from_iter([("symbol", "#"), ("agg", "sum")]),
+    );
+    assert_eq!(answer.unwrap().to_string(), "0");
+    assert!(Query::from_settings(&Settings::from_iter([("agg", "mean")])).is_err());
+    assert!(Query::from_settings(&Settings::from_iter([("symbol", "**")])).is_err());
+    assert!(Query::from_settings(&Settings::from_iter([("limit", "1")])).is_err());
+}
+
+#[test]
+fn rendering() {
+    let text = "467..114..\n...*......\n..35..633.\n";
+    let schematic = Schematic::parse(text).unwrap();
+    let marks = schematic.marks(3);
+    assert_eq!(
+        marks[0][1],
+        Mark {
+            span: 5..8,
+            style: Style::Ignored,
+            title: "114 touches no symbol".to_string()
+        }
+    );
+    assert_eq!(marks[1][0].style, Style::Gear);
+    assert_eq!(marks[1][0].title, "* at 2:4 next to [467, 35], ratio 16345");
+    assert_eq!(marks[2][1].title, "633 touches no symbol");
+
+    let plain = render(text).unwrap().replace("\x1b[0m", "");
+    let plain = ["\x1b[32m", "\x1b[2m", "\x1b[33m", "\x1b[1;31m"]
+        .iter()
+        .fold(plain, |plain, code| plain.replace(code, ""));
+    assert!(plain.starts_with(text));
+    assert!(plain.ends_with("ratio 16345\n"));
+
+    let html = render_html("1<\n").unwrap();
+    assert!(html.contains("<span class=\"part\" title=\"1 next to &lt; at 1:2\">1</span>"));
+    assert!(html.contains("title=\"&lt; at 1:2 next to [1]\">&lt;</span>"));
 }
@@ -1,92 +1,476 @@
-#![cfg(test)]
-use std::fs;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use crate::bigint::{BigInt, Number};
+use crate::checked;
+#[cfg(test)]
+use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Settings, Solution, Variant};
 
 #[derive(Debug)]
 struct Card {
     id: u32,
-    numbers: Vec<u32>,
+    numbers: HashSet<u32>,
     guesses: Vec<u32>,
 }
 
-fn parse_numbers(text: &str) -> Result<Vec<u32>, std::num::ParseIntError> {
+impl Card {
+    /// Guesses among the winning numbers
+    fn matches(&self) -> usize {
+        self.guesses
+            .iter()
+            .filter(|n| self.numbers.contains(n))
+            .count()
+    }
+}
+
+/// Scratchcards in order of their ids, which need not be contiguous
+#[derive(Debug)]
+struct Pile {
+    cards: Vec<Card>,
+}
+
+impl Pile {
+    fn new(mut cards: Vec<Card>) -> Res<Self> {
+        cards.sort_by_key(|card| card.id);
+        if let Some(pair) = cards.windows(2).find(|pair| pair[0].id == pair[1].id) {
+            return Err(format!("card {} appears twice", pair[0].id));
+        }
+        Ok(Self { cards })
+    }
+
+    /// Copies held of each card once every win is cashed in under `rule`
+    fn cascade<N: Number>(&self, rule: &dyn Rule) -> Res<Vec<N>> {
+        let mut copies = vec![N::from(1); self.cards.len()];
+        // Cash in a card only once every card winning copies of it is done
+        let mut order = (0..self.cards.len()).collect::<Vec<_>>();
+        if rule.backwards() {
+            order.reverse();
+        }
+        for idx in order {
+            let card = &self.cards[idx];
+            let matches = card.matches();
+            let each = rule.copies(matches);
+            let each = N::from_usize(each)
+                .ok_or_else(|| format!("{each} copies do not fit {}", N::NAME))?;
+            let won = checked::mul(each, copies[idx].clone(), "copies won")?;
+            for offset in rule.offsets(matches) {
+                let Some(next) = idx
+                    .checked_add_signed(offset)
+                    .filter(|&next| next < self.cards.len())
+                else {
+                    let (side, end) = match offset < 0 {
+                        true => ("first", &self.cards[0]),
+                        false => ("last", &self.cards[self.cards.len() - 1]),
+                    };
+                    return Err(format!(
+                        "card {} wins {matches} cards, past the {side} card {}",
+                        card.id, end.id
+                    ));
+                };
+                copies[next] = checked::add(copies[next].clone(), won.clone(), "copies held")?;
+            }
+        }
+        Ok(copies)
+    }
+}
+
+/// Points of a card by its number of matches
+trait Scoring {
+    fn score(&self, matches: usize) -> Res<u64>;
+}
+
+/// One point for the first match, doubled by every other, the puzzle rule
+struct Doubling;
+
+/// One point per match
+struct Linear;
+
+impl Scoring for Doubling {
+    fn score(&self, matches: usize) -> Res<u64> {
+        match matches {
+            0 => Ok(0),
+            _ => u32::try_from(matches - 1)
+                .ok()
+                .and_then(|exp| 2u64.checked_pow(exp))
+                .ok_or_else(|| format!("{matches} doubling matches overflow u64")),
+        }
+    }
+}
+
+impl Scoring for Linear {
+    fn score(&self, matches: usize) -> Res<u64> {
+        Ok(matches as u64)
+    }
+}
+
+/// Which cards a card wins copies of
+trait Rule {
+    /// Offsets in id order from the winning card, of the cards it wins
+    fn offsets(&self, matches: usize) -> RangeInclusive<isize>;
+
+    /// Whether the offsets are negative, the cards won coming before
+    fn backwards(&self) -> bool {
+        false
+    }
+
+    /// Copies won of each card
+    fn copies(&self, _matches: usize) -> usize {
+        1
+    }
+}
+
+/// A copy of each of the next `matches` cards, the puzzle rule
+struct Next;
+
+/// Like [`Next`], winning at most the given number of cards
+struct Capped(usize);
+
+/// A copy of each of the `matches` cards before
+struct Backwards;
+
+/// `matches` copies of each of the next `matches` cards
+struct Weighted;
+
+impl Rule for Next {
+    fn offsets(&self, matches: usize) -> RangeInclusive<isize> {
+        1..=matches as isize
+    }
+}
+
+impl Rule for Capped {
+    fn offsets(&self, matches: usize) -> RangeInclusive<isize> {
+        1..=matches.min(self.0) as isize
+    }
+}
+
+impl Rule for Backwards {
+    fn offsets(&self, matches: usize) -> RangeInclusive<isize> {
+        -(matches as isize)..=-1
+    }
+
+    fn backwards(&self) -> bool {
+        true
+    }
+}
+
+impl Rule for Weighted {
+    fn offsets(&self, matches: usize) -> RangeInclusive<isize> {
+        1..=matches as isize
+    }
+
+    fn copies(&self, matches: usize) -> usize {
+        matches
+    }
+}
+
+/// `variant` picks the rules, `cap` is the most cards a capped card wins,
+/// `number` counts the cards with machine or big integers
+const SETTINGS: &[&str] = &["variant", "cap", "number"];
+
+const VARIANTS: &[Variant] = &[
+    Variant {
+        part: 1,
+        name: "linear",
+        help: "one point per match instead of doubling",
+    },
+    Variant {
+        part: 2,
+        name: "capped",
+        help: "win at most day04.cap cards",
+    },
+    Variant {
+        part: 2,
+        name: "backwards",
+        help: "win copies of the cards before instead",
+    },
+    Variant {
+        part: 2,
+        name: "weighted",
+        help: "win as many copies of each card as there are matches",
+    },
+];
+
+fn scoring(variant: Option<&str>) -> Res<Box<dyn Scoring>> {
+    match variant {
+        None => Ok(Box::new(Doubling)),
+        Some("linear") => Ok(Box::new(Linear)),
+        Some(name) => Err(format!("no scoring named '{name}'")),
+    }
+}
+
+fn rule(variant: Option<&str>, settings: &Settings) -> Res<Box<dyn Rule>> {
+    match variant {
+        None => Ok(Box::new(Next)),
+        Some("capped") => {
+            let cap = settings
+                .get("cap")
+                .ok_or_else(|| "the capped variant needs day04.cap".to_string())?;
+            let cap = cap
+                .parse::<usize>()
+                .map_err(|e| format!("invalid day04.cap '{cap}': {e}"))?;
+            Ok(Box::new(Capped(cap)))
+        }
+        Some("backwards") => Ok(Box::new(Backwards)),
+        Some("weighted") => Ok(Box::new(Weighted)),
+        Some(name) => Err(format!("no cascade named '{name}'")),
+    }
+}
+
+fn parse_numbers(text: &str) -> Res<Vec<u32>> {
     text.split_whitespace()
-        .map(|num| num.parse::<u32>())
+        .map(|num| {
+            num.parse::<u32>()
+                .map_err(|e| format!("invalid number '{num}': {e}"))
+        })
         .collect()
 }
 
-fn parse(path: &str) -> Vec<Card> {
-    fs::read_to_string(path)
-        .expect("Unable to open file")
+fn parse_card(line: &str) -> Res<Card> {
+    let (card, numbers) = line
+        .split_once(':')
+        .ok_or("expected 'Card ID: numbers | guesses'")?;
+    let id = card.split_whitespace().last().ok_or("no card id")?;
+    let id = id
+        .parse::<u32>()
+        .map_err(|e| format!("invalid card id '{id}': {e}"))?;
+    let (numbers, guesses) = numbers
+        .split_once('|')
+        .ok_or_else(|| format!("card {id}: no '|' between numbers and guesses"))?;
+    Ok(Card {
+        id,
+        numbers: parse_numbers(numbers)?.into_iter().collect(),
+        guesses: parse_numbers(guesses)?,
+    })
+}
+
+fn parse(text: &str) -> Res<Pile> {
+    let cards = text
         .lines()
-        .map(|line| -> Card {
-            let (card, numbers) = line.split_once(':').expect("Unable to split card id");
-            let id = card
-                .trim()
-                .split_whitespace()
-                .last()
-                .expect("No card id")
-                .parse::<u32>()
-                .expect("Card id is not a number");
-
-            let (numbers, guesses) = numbers.split_once('|').expect("Unable to split numbers");
-
-            Card {
-                id,
-                numbers: parse_numbers(numbers).expect("Unable to parse numbers"),
-                guesses: parse_numbers(guesses).expect("Unable to parse guesses"),
-            }
+        .enumerate()
+        .map(|(idx, line)| parse_card(line).map_err(|e| format!("line {}: {e}", idx + 1)))
+        .collect::<Res<Vec<_>>>()?;
+    Pile::new(cards)
+}
+
+/// Copies of every card, and the cards winning the most copies
+fn report(pile: &Pile, rule: &dyn Rule) -> Res<String> {
+    let copies = pile.cascade::<BigInt>(rule)?;
+    let rows = pile
+        .cards
+        .iter()
+        .zip(&copies)
+        .map(|(card, held)| {
+            let matches = card.matches();
+            let cards = rule.offsets(matches).count() * rule.copies(matches);
+            let won = held.clone() * BigInt::from(cards);
+            (card, held, won)
         })
-        .collect()
+        .collect::<Vec<_>>();
+
+    let held_width = rows.iter().map(|row| row.1.to_string().len()).max();
+    let held_width = held_width.unwrap_or(0).max("copies".len());
+    let mut out = format!("card  matches  {:>held_width$}  won\n", "copies");
+    for (card, held, won) in &rows {
+        out += &format!(
+            "{:>4}  {:>7}  {held:>held_width$}  {won}\n",
+            card.id,
+            card.matches()
+        );
+    }
+
+    let mut most = rows
+        .iter()
+        .filter(|row| row.2 > BigInt::default())
+        .collect::<Vec<_>>();
+    most.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.id.cmp(&b.0.id)));
+    let most = most
+        .iter()
+        .take(5)
+        .map(|(card, _, won)| format!("card {} ({won})", card.id))
+        .collect::<Vec<_>>();
+    out += &format!("most copies won: {}\n", most.join(", "));
+    out += &format!("total cards: {}\n", copies.into_iter().sum::<BigInt>());
+    Ok(out)
+}
+
+pub(crate) struct Day04;
+
+impl Solution for Day04 {
+    fn day(&self) -> u8 {
+        4
+    }
+
+    fn settings(&self) -> &'static [&'static str] {
+        SETTINGS
+    }
+
+    fn variants(&self) -> &'static [Variant] {
+        VARIANTS
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        self.solve_with(part, input, &Settings::default())
+    }
+
+    fn solve_with(&self, part: u8, input: &str, settings: &Settings) -> Res<Answer> {
+        settings.check(self.day(), SETTINGS)?;
+        let variant = settings.variant(self, part)?;
+        let data = parse(input)?;
+        match part {
+            1 => Ok(part1(&data, scoring(variant)?.as_ref())?.into()),
+            2 => {
+                let rule = rule(variant, settings)?;
+                match settings.big()? {
+                    false => Ok(part2::<usize>(&data, rule.as_ref())?.into()),
+                    true => Ok(part2::<BigInt>(&data, rule.as_ref())?.into()),
+                }
+            }
+            _ => missing_part(self.day(), part),
+        }
+    }
+
+    fn explain(&self, part: u8, input: &str, settings: &Settings) -> Option<Res<String>> {
+        let report = || {
+            settings.check(self.day(), SETTINGS)?;
+            // The cascade is only part of part 2, part 1 shows the puzzle rule
+            let rule = match part {
+                2 => rule(settings.variant(self, part)?, settings)?,
+                _ => Box::new(Next),
+            };
+            report(&parse(input)?, rule.as_ref())
+        };
+        Some(report())
+    }
+}
+
+fn part1(data: &Pile, scoring: &dyn Scoring) -> Res<u64> {
+    let scores = data.cards.iter().map(|card| {
+        let count = card.matches();
+
+        // println!("[{}] -> {}", card.id, count);
+        scoring.score(count)
+    });
+    checked::sum(scores, "sum of scores")
+}
+
+fn part2<N: Number>(data: &Pile, rule: &dyn Rule) -> Res<N> {
+    let copies = data.cascade::<N>(rule)?;
+    checked::sum(copies.into_iter().map(Ok), "total cards")
 }
 
 #[test]
 fn task1() {
-    // let path = "src/day04/sample.txt";
-    let path = "src/day04/input.txt";
-    let data = parse(path);
+    let text = open_first(&[
+        "src/day04/input.txt",  //
+        "src/day04/sample.txt", //
+    ])
+    .unwrap();
+    let data = parse(&text).unwrap();
     // println!("[[DATA]] {data:#?}");
-    let result: i32 = data
-        .iter()
-        .map(|card| {
-            let count = card
-                .numbers
-                .iter()
-                .filter(|n| card.guesses.contains(n))
-                .count();
-
-            // println!("[{}] -> {}", card.id, count);
-            1 << count >> 1
-        })
-        .sum();
+    let result = part1(&data, &Doubling).unwrap();
     println!("Result: {result}");
 }
 
 #[test]
 fn task2() {
-    // let path = "src/day04/sample.txt";
-    let path = "src/day04/input.txt";
-    let data = parse(path);
+    let text = open_first(&[
+        "src/day04/input.txt",  //
+        "src/day04/sample.txt", //
+    ])
+    .unwrap();
+    let data = parse(&text).unwrap();
     // println!("[[DATA]] {data:#?}");
 
-    let mut computed: Vec<usize> = Vec::with_capacity(data.len());
+    let result: usize = part2(&data, &Next).unwrap();
+    println!("Result: {result}");
+}
 
-    let result: usize = data
-        .iter()
-        .rev()
-        .map(|card| {
-            let count = card
-                .numbers
-                .iter()
-                .filter(|n| card.guesses.contains(n))
-                .count();
-
-            let rpos = data.len() - card.id as usize;
-            let aggregate: usize = (0..count).map(|i| computed[rpos - i - 1]).sum();
-            let total = count + aggregate;
-            computed.push(total);
-            return total + 1;
+#[test]
+fn copies() {
+    // Every card wins a copy of all the later ones, doubling the copies of each next card
+    let text = (1..=100)
+        .map(|id| {
+            let numbers = (id..100)
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("Card {id}: {numbers} | {numbers}")
         })
-        .sum();
+        .collect::<Vec<_>>()
+        .join("\n");
+    let data = parse(&text).unwrap();
+    let expected = (0..100).fold(BigInt::from(0), |sum, _| {
+        sum * BigInt::from(2) + BigInt::from(1)
+    });
+    assert_eq!(part2::<BigInt>(&data, &Next), Ok(expected));
+}
 
-    println!("Result: {result}");
+#[test]
+fn gaps() {
+    // Ids with gaps and out of order, wins go to the next cards by id
+    let data = parse("Card 10: 1 2 | 1 2\nCard 3: 5 | 5\nCard 30: 7 | 8\nCard 20: 1 | 2").unwrap();
+    assert_eq!(
+        data.cards.iter().map(|card| card.id).collect::<Vec<_>>(),
+        [3, 10, 20, 30]
+    );
+    assert_eq!(data.cascade::<usize>(&Next), Ok(vec![1, 2, 3, 3]));
+    assert_eq!(part2::<usize>(&data, &Next), Ok(9));
+
+    let report = report(&data, &Next).unwrap();
+    assert!(report.contains("most copies won: card 10 (4), card 3 (1)\n"));
+    assert!(report.ends_with("total cards: 9\n"));
+
+    let past = parse("Card 1: 1 2 | 1 2\nCard 2: 3 | 4").unwrap();
+    assert_eq!(
+        past.cascade::<usize>(&Next),
+        Err("card 1 wins 2 cards, past the last card 2".to_string())
+    );
+    assert!(parse("Card 1: 1 | 2\nCard 1: 1 | 2").is_err());
+    assert_eq!(
+        parse("Card 1: 1 | 2\nCard x: 1 | 2").map(|_| ()),
+        Err("line 2: invalid card id 'x': invalid digit found in string".to_string())
+    );
+    assert!(parse("Card 1: 1 2").is_err());
+    assert!(parse("Card 1 1 | 2").is_err());
+    assert!(parse("Card 1: 1 | -2").is_err());
+}
+
+#[test]
+fn variants() {
+    let text = open_first(&["src/day04/sample.txt"]).unwrap();
+    let data = parse(&text).unwrap();
+    assert_eq!(part1(&data, &Linear), Ok(4 + 2 + 2 + 1));
+    assert_eq!(
+        Doubling.score(65),
+        Err("65 doubling matches overflow u64".to_string())
+    );
+
+    assert_eq!(
+        data.cascade::<usize>(&Capped(1)),
+        Ok(vec![1, 2, 3, 4, 5, 1])
+    );
+    assert_eq!(data.cascade::<usize>(&Capped(10)), data.cascade(&Next));
+    // Card 1 wins 4 copies of each of cards 2 to 5, card 2 then holds 5 and wins 2 copies each
+    assert_eq!(data.cascade::<usize>(&Weighted).unwrap()[..3], [1, 5, 15]);
+    assert_eq!(
+        data.cascade::<usize>(&Backwards),
+        Err("card 2 wins 2 cards, past the first card 1".to_string())
+    );
+    let mirrored = parse("Card 1: 7 | 8\nCard 2: 1 | 1\nCard 3: 1 2 | 1 2").unwrap();
+    assert_eq!(mirrored.cascade::<usize>(&Backwards), Ok(vec![4, 2, 1]));
+
+    let pick = |pairs: &[(&str, &str)]| Settings::from_iter(pairs.iter().copied());
+    let answer = |part, pairs: &[(&str, &str)]| {
+        Day04
+            .solve_with(part, &text, &pick(pairs))
+            .map(|a| a.to_string())
+    };
+    assert_eq!(answer(1, &[("variant", "linear")]), Ok("9".to_string()));
+    assert_eq!(
+        answer(2, &[("variant", "capped"), ("cap", "1")]),
+        Ok("16".to_string())
+    );
+    assert!(answer(2, &[("variant", "capped")]).is_err());
+    assert!(answer(2, &[("variant", "linear")]).is_err());
 }
@@ -1,18 +1,13 @@
-#![cfg(test)]
-
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::anonymise::Rng;
+use crate::checked;
+use crate::explore::Simulation;
+use crate::interval::{IntervalMap, IntervalSet, Piece};
+#[cfg(test)]
 use crate::open_first;
-
-#[derive(Debug)]
-struct Range {
-    src: usize,
-    len: usize,
-}
-
-impl Range {
-    fn end(&self) -> usize {
-        self.src + self.len
-    }
-}
+use crate::solution::{missing_part, Answer, Res, Solution};
 
 #[derive(Debug)]
 struct RangeMap {
@@ -22,30 +17,37 @@ struct RangeMap {
 }
 
 impl RangeMap {
-    fn parse(line: &str) -> Self {
-        let mut iter = line
+    fn parse(line: &str) -> Res<Self> {
+        let nums = line
             .split_whitespace()
-            .map(|num| num.parse::<usize>().expect("Unable to parse number"));
-        let dst = iter.next().expect("No destination");
-        let src = iter.next().expect("No source");
-        let len = iter.next().expect("No length");
-        assert_eq!(iter.count(), 0, "Unexpected numbers");
-        return Self { dst, src, len };
-    }
-
-    fn map(&self, src: usize) -> Option<usize> {
-        src.checked_sub(self.src)
-            .filter(|&len| len < self.len)
-            .map(|len| self.dst + len)
+            .map(|num| {
+                num.parse::<usize>()
+                    .map_err(|e| format!("invalid number '{num}': {e}"))
+            })
+            .collect::<Res<Vec<_>>>()?;
+        let [dst, src, len] = nums[..] else {
+            return Err(format!(
+                "expected destination, source and length, got '{line}'"
+            ));
+        };
+        Ok(Self { dst, src, len })
     }
 
-    fn src_end(&self) -> usize {
-        self.src + self.len
+    fn piece(&self) -> Res<Piece> {
+        let [dst, src, len] = [self.dst, self.src, self.len].map(value);
+        let (dst, src, len) = (dst?, src?, len?);
+        // Both ends fit, so every image and offset composed from them does too
+        checked::add(dst, len, "destination end")?;
+        Ok(Piece {
+            range: src..checked::add(src, len, "source end")?,
+            offset: dst - src,
+        })
     }
+}
 
-    fn overlap_range(&self, range: &Range) -> bool {
-        self.src < range.end() && range.src < self.src_end()
-    }
+/// `num` as a value of the chain
+fn value(num: usize) -> Res<i64> {
+    i64::try_from(num).map_err(|_| format!("{num} does not fit i64"))
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -84,71 +86,26 @@ struct ConversionMap {
 }
 
 impl ConversionMap {
-    fn parse(header: &str) -> Self {
-        assert!(header.ends_with(" map:"), "Missing map declaration");
-        let header = header.trim_end_matches(" map:");
-        let (src, dst) = header.split_once("-to-").expect("Missing type declaraion");
-        let src = EntryType::parse(src).expect("Invalid source");
-        let dst = EntryType::parse(dst).expect("Unable to parse destination");
+    fn parse(header: &str) -> Res<Self> {
+        let types = header
+            .strip_suffix(" map:")
+            .ok_or_else(|| format!("expected a map declaration, got '{header}'"))?;
+        let (src, dst) = types
+            .split_once("-to-")
+            .ok_or_else(|| format!("expected source-to-destination, got '{types}'"))?;
+        let src = EntryType::parse(src).ok_or_else(|| format!("invalid source '{src}'"))?;
+        let dst = EntryType::parse(dst).ok_or_else(|| format!("invalid destination '{dst}'"))?;
         let map = Vec::new();
-        return ConversionMap { src, dst, map };
+        Ok(ConversionMap { src, dst, map })
     }
 
-    fn convert(&self, src: usize) -> usize {
-        self.map
+    fn interval_map(&self) -> Res<IntervalMap> {
+        let pieces = self
+            .map
             .iter()
-            .find_map(|range| range.map(src))
-            .unwrap_or(src)
-    }
-
-    fn convert_ranges(&self, src: &mut Vec<Range>, dst: &mut Vec<Range>) {
-        let min = std::cmp::min;
-        let max = std::cmp::max;
-        while let Some(range) = src.pop() {
-            // Find first range with overlap
-            let Some(map) = self.map.iter().find(|map| map.overlap_range(&range)) else {
-                dst.push(range);
-                continue;
-            };
-
-            // Slice ranges
-            let pos = max(range.src, map.src);
-            let end = min(range.end(), map.src_end());
-
-            /*
-            println!(
-                "{}..{} </> {}..{}",
-                map.src,
-                map.src_end(),
-                range.src,
-                range.end(),
-            );
-            */
-
-            // println!("overlap: {}..{}", pos, end);
-
-            if pos > range.src {
-                // println!("prefix: {}..{}", range.src, pos);
-                src.push(Range {
-                    src: range.src,
-                    len: pos - range.src,
-                });
-            }
-
-            if end < range.end() {
-                // println!("postfix: {}..{}", end, range.end());
-                src.push(Range {
-                    src: end,
-                    len: range.end() - end,
-                });
-            }
-
-            let offset = pos - map.src;
-            dst.push(Range {
-                src: map.dst + offset,
-                len: end - pos,
-            });
-        }
+            .map(RangeMap::piece)
+            .collect::<Res<Vec<_>>>();
+        IntervalMap::new(pieces?).map_err(|e| format!("{:?}-to-{:?} map: {e}", self.src, self.dst))
     }
 }
 
@@ -159,24 +116,25 @@ struct Almanac {
 }
 
 impl Almanac {
-    fn parse(data: &str) -> Self {
-        let mut iter = data.lines();
+    fn parse(data: &str) -> Res<Self> {
+        let mut iter = data.lines().enumerate();
         let seeds = iter
             .next()
-            .expect("Missing entry line")
-            .split_once(':')
-            .expect("Missing seeds declaration")
-            .1
-            .trim()
+            .and_then(|(_, line)| line.strip_prefix("seeds:"))
+            .ok_or("missing seeds declaration")?
             .split_whitespace()
-            .map(|num| num.parse::<usize>().expect("Unable to parse seed"))
-            .collect::<Vec<_>>();
+            .map(|num| {
+                num.parse::<usize>()
+                    .map_err(|e| format!("invalid seed '{num}': {e}"))
+            })
+            .collect::<Res<Vec<_>>>()?;
 
         let mut maps = Vec::new();
         let mut map: Option<ConversionMap> = None;
 
-        while let Some(line) = iter.next() {
+        for (idx, line) in iter {
             let line = line.trim();
+            let context = |e: String| format!("line {}: {e}", idx + 1);
 
             // Separation
             if line.is_empty() {
@@ -188,21 +146,250 @@ impl Almanac {
 
             // Parse header if no map
             let Some(map) = &mut map else {
-                map.replace(ConversionMap::parse(line));
+                map.replace(ConversionMap::parse(line).map_err(context)?);
                 continue;
             };
 
-            map.map.push(RangeMap::parse(line));
+            map.map.push(RangeMap::parse(line).map_err(context)?);
         }
 
         if let Some(map) = map.take() {
             maps.push(map);
         }
 
-        return Self { seeds, maps };
+        Ok(Self { seeds, maps })
+    }
+
+    /// The conversions from seed to location, in order
+    fn stages(&self) -> Res<Vec<Stage>> {
+        let mut curr = EntryType::Seed;
+        let mut stages = Vec::new();
+        while let Some(conv) = self.maps.iter().find(|conv| conv.src == curr) {
+            if stages.len() == self.maps.len() {
+                return Err(format!("conversions loop back to {curr:?}"));
+            }
+            stages.push(Stage {
+                src: conv.src,
+                dst: conv.dst,
+                map: conv.interval_map()?,
+            });
+            curr = conv.dst;
+        }
+        if curr != EntryType::Location {
+            return Err(format!("conversions end at {curr:?}, not at Location"));
+        }
+        Ok(stages)
+    }
+
+    /// Every conversion from seed to location composed into one map
+    fn chain(&self) -> Res<IntervalMap> {
+        Ok(self
+            .stages()?
+            .iter()
+            .fold(IntervalMap::default(), |chain, stage| {
+                chain.then(&stage.map)
+            }))
+    }
+
+    /// The seed ranges of task2
+    fn seed_ranges(&self) -> Res<IntervalSet> {
+        if !self.seeds.len().is_multiple_of(2) {
+            return Err(format!(
+                "expected pairs of range start and length, got {} seeds",
+                self.seeds.len()
+            ));
+        }
+        self.seeds
+            .chunks(2)
+            .map(|view| {
+                let start = value(view[0])?;
+                Ok(start..checked::add(start, value(view[1])?, "seed range end")?)
+            })
+            .collect()
+    }
+}
+
+/// A conversion of the chain, ready to apply
+#[derive(Debug)]
+struct Stage {
+    src: EntryType,
+    dst: EntryType,
+    map: IntervalMap,
+}
+
+/// Seed ranges of task2, split by one conversion stage per step
+#[derive(Clone)]
+pub(crate) struct Stages {
+    stages: Rc<Vec<Stage>>,
+    /// Index of the next stage
+    next: usize,
+    ranges: IntervalSet,
+}
+
+impl Stages {
+    pub(crate) fn parse(text: &str) -> Res<Self> {
+        let almanac = Almanac::parse(text)?;
+        Ok(Self {
+            stages: Rc::new(almanac.stages()?),
+            next: 0,
+            ranges: almanac.seed_ranges()?,
+        })
+    }
+
+    fn curr(&self) -> EntryType {
+        match self.next.checked_sub(1) {
+            Some(prev) => self.stages[prev].dst,
+            None => EntryType::Seed,
+        }
+    }
+}
+
+impl Simulation for Stages {
+    fn step(&mut self) -> bool {
+        let Some(stage) = self.stages.get(self.next) else {
+            return false;
+        };
+        self.ranges = stage.map.apply(&self.ranges);
+        self.next += 1;
+        true
+    }
+}
+
+impl std::fmt::Display for Stages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranges = self.ranges.ranges();
+        writeln!(f, "{:?}: {} ranges", self.curr(), ranges.len())?;
+        for range in ranges {
+            writeln!(
+                f,
+                "  {:>12}..{:<12} ({})",
+                range.start,
+                range.end,
+                range.end - range.start
+            )?;
+        }
+        match self.stages.get(self.next) {
+            Some(stage) => write!(f, "next: {:?} -> {:?}", stage.src, stage.dst),
+            None => write!(f, "next: -"),
+        }
+    }
+}
+
+/// Move the values of every stage by a random offset of their own, and shuffle the lines of each map.
+///
+/// The gaps of each map become identity lines, so unmapped values follow the offsets too.
+/// Locations are not moved, and the seed range lengths, which task1 reads as seeds, get lines
+/// of their own below the moved seeds, so both answers stay the same.
+pub(crate) fn anonymise(text: &str, rng: &mut Rng) -> Res<String> {
+    let data = Almanac::parse(text)?;
+    let stages = data.stages()?;
+    data.seed_ranges()?;
+
+    let mut lengths = data
+        .seeds
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .copied()
+        .collect::<Vec<_>>();
+    lengths.sort();
+    lengths.dedup();
+    let end = data
+        .seeds
+        .chunks(2)
+        .map(|pair| pair[0] + pair[1])
+        .chain(
+            data.maps
+                .iter()
+                .flat_map(|conv| &conv.map)
+                .map(|map| map.src.max(map.dst) + map.len),
+        )
+        .max()
+        .unwrap_or(0) as i64;
+    let mut offset = |typ: EntryType| match typ {
+        EntryType::Seed => {
+            lengths.last().map_or(0, |len| *len as i64 + 1) + rng.below(1 << 32) as i64
+        }
+        EntryType::Location => 0,
+        _ => rng.below(1 << 32) as i64,
+    };
+    let mut offsets = vec![offset(EntryType::Seed)];
+    offsets.extend(stages.iter().map(|stage| offset(stage.dst)));
+
+    let seeds = data
+        .seeds
+        .chunks(2)
+        .flat_map(|pair| [pair[0] as i64 + offsets[0], pair[1] as i64])
+        .map(|num| num.to_string())
+        .collect::<Vec<_>>();
+    let mut out = format!("seeds: {}\n", seeds.join(" "));
+
+    let name = |typ: EntryType| format!("{typ:?}").to_lowercase();
+    for (idx, stage) in stages.iter().enumerate() {
+        let (src, dst) = (offsets[idx], offsets[idx + 1]);
+        out += &format!("\n{}-to-{} map:\n", name(stage.src), name(stage.dst));
+        let mut lines = stage
+            .map
+            .cut(0..end)
+            .map(|piece| {
+                let Range { start, end } = piece.range;
+                format!(
+                    "{} {} {}\n",
+                    start + piece.offset + dst,
+                    start + src,
+                    end - start
+                )
+            })
+            .collect::<Vec<_>>();
+        if idx == 0 {
+            lines.extend(lengths.iter().map(|&len| {
+                let len = len as i64;
+                format!("{} {len} 1\n", stage.map.get(len) + dst)
+            }));
+        }
+        rng.shuffle(&mut lines);
+        out.extend(lines);
+    }
+    Ok(out)
+}
+
+pub(crate) struct Day05;
+
+impl Solution for Day05 {
+    fn day(&self) -> u8 {
+        5
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        let data = Almanac::parse(input)?;
+        match part {
+            1 => Ok(part1(&data)?.into()),
+            2 => Ok(part2(&data)?.into()),
+            _ => missing_part(self.day(), part),
+        }
     }
 }
 
+fn part1(data: &Almanac) -> Res<i64> {
+    let chain = data.chain()?;
+    let locations = data
+        .seeds
+        .iter()
+        .map(|&seed| Ok(chain.get(value(seed)?)))
+        .collect::<Res<Vec<_>>>()?;
+    locations
+        .into_iter()
+        .min()
+        .ok_or_else(|| "Missing output value".to_string())
+}
+
+fn part2(data: &Almanac) -> Res<i64> {
+    data.chain()?
+        .apply(&data.seed_ranges()?)
+        .min()
+        .ok_or_else(|| "Missing output value".to_string())
+}
+
 #[test]
 fn task1() {
     let data = open_first(&[
@@ -211,24 +398,14 @@ fn task1() {
         //
     ])
     .unwrap();
-    let data = Almanac::parse(&data);
+    let data = Almanac::parse(&data).unwrap();
     // println!("Data: {data:#?}");
 
-    let mut curr = EntryType::Seed;
-    let mut vals = data.seeds.clone();
-
-    while let Some(conv) = data.maps.iter().find(|conv| conv.src == curr) {
-        println!("{:?} -> {:?}", conv.src, conv.dst);
-        for val in &mut vals {
-            *val = conv.convert(*val);
-        }
-        curr = conv.dst;
-    }
-
-    assert_eq!(curr, EntryType::Location);
-
-    let result = vals.iter().min().expect("Missing output value");
+    let result = part1(&data).unwrap();
     println!("Result: {}", result);
+
+    let sample = open_first(&["src/day05/sample.txt"]).unwrap();
+    assert_eq!(part1(&Almanac::parse(&sample).unwrap()), Ok(35));
 }
 
 #[test]
@@ -239,34 +416,32 @@ fn task2() {
         //
     ])
     .unwrap();
-    let data = Almanac::parse(&data);
+    let data = Almanac::parse(&data).unwrap();
     // println!("Data: {data:#?}");
 
-    let mut curr = EntryType::Seed;
-    let mut src = data
-        .seeds
-        .chunks(2)
-        .map(|view| Range {
-            src: view[0],
-            len: view[1],
-        })
-        .collect::<Vec<_>>();
-    let mut dst = Vec::with_capacity(src.capacity());
-
-    while let Some(conv) = data.maps.iter().find(|conv| conv.src == curr) {
-        println!("{:?} -> {:?}", conv.src, conv.dst);
-        conv.convert_ranges(&mut src, &mut dst);
-        std::mem::swap(&mut src, &mut dst);
-        dst.clear();
-        curr = conv.dst;
-    }
+    let result = part2(&data).unwrap();
+    println!("Result: {}", result);
 
-    assert_eq!(curr, EntryType::Location);
+    let sample = open_first(&["src/day05/sample.txt"]).unwrap();
+    assert_eq!(part2(&Almanac::parse(&sample).unwrap()), Ok(46));
+}
 
-    let result = src
-        .iter()
-        .map(|range| range.src)
-        .min()
-        .expect("Missing output value");
-    println!("Result: {}", result);
+#[test]
+fn malformed() {
+    let sample = open_first(&["src/day05/sample.txt"]).unwrap();
+    let odd = sample.replacen("79 14 55 13", "79 14 55", 1);
+    assert!(part1(&Almanac::parse(&odd).unwrap()).is_ok());
+    assert!(part2(&Almanac::parse(&odd).unwrap()).is_err());
+    let overlap = sample.replacen("50 98 2", "50 97 2", 1);
+    assert!(Stages::parse(&overlap).is_err());
+    assert!(Almanac::parse(&sample.replacen("52 50 48", "52 50", 1)).is_err());
+    assert!(Almanac::parse(&sample.replacen("seed-to-soil", "seed-to-dirt", 1)).is_err());
+
+    let mut stages = Stages::parse(&sample).unwrap();
+    let mut steps = 0;
+    while stages.step() {
+        steps += 1;
+    }
+    assert_eq!((steps, stages.curr()), (7, EntryType::Location));
+    assert_eq!(stages.ranges.min(), Some(46));
 }
@@ -1,73 +1,128 @@
-#![cfg(test)]
-
+use crate::checked::{self, Checked};
+#[cfg(test)]
 use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Solution};
 
-fn distance_of_u32(duration: u32, charge: u32) -> u32 {
-    let remainder = duration - charge;
-    return remainder * charge;
+fn distance_of<T: Checked + Copy>(duration: T, charge: T) -> Res<T> {
+    let remainder = checked::sub(duration, charge, "remainder")?;
+    checked::mul(remainder, charge, "remainder * charge")
 }
 
-fn distance_of_usize(duration: usize, charge: usize) -> usize {
-    let remainder = duration - charge;
-    return remainder * charge;
+/// First of `charges` that does not beat the `distance` record
+fn first_short<T: Checked + Copy + Ord>(
+    charges: impl Iterator<Item = T>,
+    duration: T,
+    distance: T,
+) -> Res<T> {
+    for charge in charges {
+        if distance_of(duration, charge)? <= distance {
+            return Ok(charge);
+        }
+    }
+    Err(format!("every charge beats {distance}"))
 }
 
-#[test]
-fn task1() {
-    let data = open_first(&[
-        "src/day06/input.txt",
-        "src/day06/sample.txt",
-        //
-    ])
-    .unwrap();
+fn impossible(distance: impl std::fmt::Display) -> String {
+    format!("middle charge does not beat {distance}, case is impossible")
+}
+
+pub(crate) struct Day06;
+
+impl Solution for Day06 {
+    fn day(&self) -> u8 {
+        6
+    }
 
-    let numbers = |str: &str| -> Vec<u32> {
-        str.split_once(':')
-            .expect("Missing separator")
-            .1
-            .trim()
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        match part {
+            1 => Ok(part1(input)?.into()),
+            2 => Ok(part2(input)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+/// The numbers after the `name:` label of the next line
+fn values<'a>(lines: &mut impl Iterator<Item = &'a str>, name: &str) -> Res<&'a str> {
+    let line = lines.next().ok_or_else(|| format!("missing {name}"))?;
+    line.split_once(':')
+        .map(|(_, values)| values)
+        .ok_or_else(|| format!("missing separator in {name} '{line}'"))
+}
+
+fn part1(data: &str) -> Res<u32> {
+    let numbers = |values: &str| -> Res<Vec<u32>> {
+        values
             .split_whitespace()
-            .map(|num| num.parse::<u32>().expect("Not a number"))
+            .map(|num| {
+                num.parse::<u32>()
+                    .map_err(|e| format!("invalid number '{num}': {e}"))
+            })
             .collect()
     };
 
     let mut iter = data.lines();
-    let durations = numbers(iter.next().expect("Missing durations"));
-    let distances = numbers(iter.next().expect("Missing distances"));
+    let durations = numbers(values(&mut iter, "durations")?)?;
+    let distances = numbers(values(&mut iter, "distances")?)?;
+    if durations.len() != distances.len() {
+        return Err(format!(
+            "{} durations but {} distances",
+            durations.len(),
+            distances.len()
+        ));
+    }
+
+    let ways = Iterator::zip(distances.iter(), durations.iter()).map(|(&distance, &duration)| {
+        // println!("duration: {:3}", duration);
+        // println!("distance: {:3}", distance);
+
+        let middle = duration >> 1;
+        if distance_of(duration, middle)? <= distance {
+            return Err(impossible(distance));
+        }
+
+        let min = first_short((0..middle).rev(), duration, distance)?;
+        let max = first_short(middle..duration, duration, distance)?;
+
+        let result = checked::sub(checked::sub(max, min, "max - min")?, 1, "ways")?;
+
+        // println!("middle: {middle} result: {result}");
+
+        Ok(result)
+    });
+    checked::product(ways, "product of ways")
+}
 
-    let result: u32 = Iterator::zip(distances.iter(), durations.iter())
-        .map(|(&distance, &duration)| {
-            //
-            println!("duration: {:3}", duration);
-            println!("distance: {:3}", distance);
+fn part2(data: &str) -> Res<usize> {
+    let number = |values: &str| -> Res<usize> {
+        let digits = values.split_whitespace().collect::<String>();
+        digits
+            .parse::<usize>()
+            .map_err(|e| format!("invalid number '{digits}': {e}"))
+    };
 
-            let middle = duration >> 1;
-            assert!(
-                distance_of_u32(duration, middle) > distance,
-                "Middle charge is too short, case is impossible"
-            );
+    let mut iter = data.lines();
+    let duration = number(values(&mut iter, "durations")?)?;
+    let distance = number(values(&mut iter, "distances")?)?;
 
-            let min = (0..middle)
-                .rev()
-                .find(|charge| distance_of_u32(duration, *charge) <= distance)
-                .unwrap();
-            let max = (middle..duration)
-                .find(|charge| distance_of_u32(duration, *charge) <= distance)
-                .unwrap();
+    // println!("duration: {:3}", duration);
+    // println!("distance: {:3}", distance);
 
-            let result = max - min - 1;
+    let middle = duration >> 1;
+    if distance_of(duration, middle)? <= distance {
+        return Err(impossible(distance));
+    }
 
-            println!("middle: {middle} result: {result}");
+    let min = first_short((0..middle).rev(), duration, distance)?;
+    let max = first_short(middle..duration, duration, distance)?;
 
-            return result;
-        })
-        .product();
+    // println!("middle: {middle}");
 
-    println!("Result: {}", result);
+    checked::sub(checked::sub(max, min, "max - min")?, 1, "ways")
 }
 
 #[test]
-fn task2() {
+fn task1() {
     let data = open_first(&[
         "src/day06/input.txt",
         "src/day06/sample.txt",
@@ -75,42 +130,31 @@ fn task2() {
     ])
     .unwrap();
 
-    let number = |str: &str| -> usize {
-        str.split_once(':')
-            .expect("Missing separator")
-            .1
-            .trim()
-            .split_whitespace()
-            .collect::<String>()
-            .parse::<usize>()
-            .expect("Not a number")
-    };
-
-    let mut iter = data.lines();
-    let duration = number(iter.next().expect("Missing durations"));
-    let distance = number(iter.next().expect("Missing distances"));
-
-    //
-    println!("duration: {:3}", duration);
-    println!("distance: {:3}", distance);
-
-    let middle = duration >> 1;
-    assert!(
-        distance_of_usize(duration, middle) > distance,
-        "Middle charge is too short, case is impossible"
-    );
-
-    let min = (0..middle)
-        .rev()
-        .find(|charge| distance_of_usize(duration, *charge) <= distance)
-        .unwrap();
-    let max = (middle..duration)
-        .find(|charge| distance_of_usize(duration, *charge) <= distance)
-        .unwrap();
-
-    let result = max - min - 1;
+    let result = part1(&data).unwrap();
+    println!("Result: {}", result);
+}
 
-    println!("middle: {middle}");
+#[test]
+fn task2() {
+    let data = open_first(&[
+        "src/day06/input.txt",
+        "src/day06/sample.txt",
+        //
+    ])
+    .unwrap();
 
+    let result = part2(&data).unwrap();
     println!("Result: {}", result);
 }
+
+#[test]
+fn malformed() {
+    assert_eq!(part1("Time: 7 15\nDistance: 9 40\n"), Ok(4 * 8));
+    assert!(part1("Time: 7 15\n").is_err());
+    assert!(part1("Time 7 15\nDistance: 9 40\n").is_err());
+    assert!(part1("Time: 7 x\nDistance: 9 40\n").is_err());
+    assert!(part1("Time: 7 15\nDistance: 9\n").is_err());
+    assert!(part2("Time: 7 15\nDistance: 9 40\n").is_ok());
+    assert!(part2("Time: 7 1x\nDistance: 9 40\n").is_err());
+    assert!(part2("").is_err());
+}
@@ -1,6 +1,8 @@
-#![cfg(test)]
-
+use crate::anonymise::Rng;
+use crate::checked;
+#[cfg(test)]
 use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Solution};
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum CardTypeStandard {
@@ -175,30 +177,168 @@ struct Bid<CardType> {
     bid: usize,
 }
 
-#[test]
-fn task1() {
+/// Sum of every bid times its rank, for bids sorted from weakest
+fn winnings<CardType>(bids: &[Bid<CardType>]) -> Res<usize> {
+    let wins = bids
+        .iter()
+        .enumerate()
+        .map(|(i, b)| checked::mul(b.bid, i + 1, "bid * rank"));
+    checked::sum(wins, "total winnings")
+}
+
+/// The five cards of `hand`
+fn cards<CardType>(hand: &str, parse: fn(char) -> Option<CardType>) -> Res<Hand<CardType>> {
+    hand.chars()
+        .map(|c| parse(c).ok_or_else(|| format!("invalid card '{c}'")))
+        .collect::<Res<Vec<_>>>()?
+        .try_into()
+        .map_err(|_| format!("expected a hand of 5 cards, got '{hand}'"))
+}
+
+/// The hand under the rules of both parts
+type Ranked = (HandType<CardTypeStandard>, HandType<CardTypeJokers>);
+
+fn ranked(hand: &[char]) -> Res<Ranked> {
+    let hand = hand.iter().collect::<String>();
+    Ok((
+        HandType::<CardTypeStandard>::parse(cards(&hand, CardTypeStandard::parse)?),
+        HandType::<CardTypeJokers>::parse(cards(&hand, CardTypeJokers::parse)?),
+    ))
+}
+
+const LABELS: [char; 13] = [
+    'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
+];
+
+/// Rewrite the hands so every bid keeps its rank in both parts, and shuffle the lines.
+///
+/// A hand changes by relabelling all copies of one of its cards, kept only when it
+/// stays of the same type and between its neighbours in both rankings. Equal hands
+/// rank by line, so they must all end up different before the lines can move.
+pub(crate) fn anonymise(text: &str, rng: &mut Rng) -> Res<String> {
+    let mut lines = text
+        .lines()
+        .map(|line| {
+            let (hand, bid) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("expected a hand and a bid, got '{line}'"))?;
+            let hand = hand.chars().collect::<Vec<_>>();
+            Ok((ranked(&hand)?, hand, bid))
+        })
+        .collect::<Res<Vec<_>>>()?;
+
+    // Lines weakest first in each part, ties by line like the stable sort of the solver
+    let mut orders = [(); 2].map(|_| (0..lines.len()).collect::<Vec<_>>());
+    orders[0].sort_by_key(|&idx| std::cmp::Reverse(lines[idx].0 .0));
+    orders[1].sort_by_key(|&idx| std::cmp::Reverse(lines[idx].0 .1));
+    let mut places = [vec![0; lines.len()], vec![0; lines.len()]];
+    for (order, places) in orders.iter().zip(&mut places) {
+        for (place, &idx) in order.iter().enumerate() {
+            places[idx] = place;
+        }
+    }
+
+    // Neighbours in `order` around `place`, weaker then stronger
+    let around = |order: &[usize], place: usize| {
+        let weaker = place.checked_sub(1).map(|place| order[place]);
+        (weaker, order.get(place + 1).copied())
+    };
+    let ties = |lines: &[(Ranked, Vec<char>, &str)], idx: usize, hand: &Ranked| {
+        let (weaker1, stronger1) = around(&orders[0], places[0][idx]);
+        let (weaker2, stronger2) = around(&orders[1], places[1][idx]);
+        let fits = weaker1.is_none_or(|other| lines[other].0 .0 >= hand.0)
+            && stronger1.is_none_or(|other| lines[other].0 .0 <= hand.0)
+            && weaker2.is_none_or(|other| lines[other].0 .1 >= hand.1)
+            && stronger2.is_none_or(|other| lines[other].0 .1 <= hand.1);
+        let count = [weaker1, stronger1]
+            .into_iter()
+            .flatten()
+            .filter(|&other| lines[other].0 .0 == hand.0)
+            .count()
+            + [weaker2, stronger2]
+                .into_iter()
+                .flatten()
+                .filter(|&other| lines[other].0 .1 == hand.1)
+                .count();
+        fits.then_some(count)
+    };
+
+    for _ in 0..64 * lines.len() {
+        let idx = rng.below(lines.len());
+        let hand = &lines[idx].1;
+        let (from, to) = (hand[rng.below(5)], LABELS[rng.below(LABELS.len())]);
+        if hand.contains(&to) {
+            continue;
+        }
+        let changed = hand
+            .iter()
+            .map(|&c| if c == from { to } else { c })
+            .collect::<Vec<_>>();
+        let hand = ranked(&changed)?;
+        let same_types = std::mem::discriminant(&hand.0)
+            == std::mem::discriminant(&lines[idx].0 .0)
+            && std::mem::discriminant(&hand.1) == std::mem::discriminant(&lines[idx].0 .1);
+        let before = ties(&lines, idx, &lines[idx].0);
+        if same_types && ties(&lines, idx, &hand).is_some_and(|count| Some(count) <= before) {
+            lines[idx].0 = hand;
+            lines[idx].1 = changed;
+        }
+    }
+
+    let tied = orders.iter().enumerate().any(|(part, order)| {
+        order.windows(2).any(|pair| match part {
+            0 => lines[pair[0]].0 .0 == lines[pair[1]].0 .0,
+            _ => lines[pair[0]].0 .1 == lines[pair[1]].0 .1,
+        })
+    });
+    if tied {
+        return Err("could not tell equal hands apart".to_string());
+    }
+
+    let mut lines = lines
+        .iter()
+        .map(|(_, hand, bid)| format!("{} {bid}\n", hand.iter().collect::<String>()))
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut lines);
+    let out = lines.concat();
+    if out.lines().eq(text.lines()) {
+        return Err("anonymised input is the original".to_string());
+    }
+    Ok(out)
+}
+
+pub(crate) struct Day07;
+
+impl Solution for Day07 {
+    fn day(&self) -> u8 {
+        7
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        match part {
+            1 => Ok(part1(input)?.into()),
+            2 => Ok(part2(input)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+fn part1(text: &str) -> Res<usize> {
     type CardType = CardTypeStandard;
 
-    let mut data = open_first(&[
-        "src/day07/input.txt",
-        "src/day07/sample.txt",
-        //
-    ])
-    .unwrap()
-    .lines()
-    .map(|line| {
-        let (hand, bid) = line.split_once(' ').expect("hand-bid separation");
-        let bid = bid.parse::<usize>().unwrap();
-        let hand: Hand<CardType> = hand
-            .chars()
-            .map(|c| CardType::parse(c).expect("valid card"))
-            .collect::<Vec<_>>()
-            .try_into()
-            .expect("A valid hand of 5 cards");
-        let hand = HandType::<CardType>::parse(hand);
-        return Bid { hand, bid };
-    })
-    .collect::<Vec<_>>();
+    let mut data = text
+        .lines()
+        .map(|line| {
+            let (hand, bid) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("expected a hand and a bid, got '{line}'"))?;
+            let bid = bid
+                .parse::<usize>()
+                .map_err(|e| format!("invalid bid '{bid}': {e}"))?;
+            let hand = HandType::<CardType>::parse(cards(hand, CardType::parse)?);
+            Ok(Bid { hand, bid })
+        })
+        .collect::<Res<Vec<_>>>()?;
 
     // println!("{:#?}", data);
 
@@ -206,34 +346,25 @@ fn task1() {
 
     // println!("{:#?}", data);
 
-    let result: usize = data.iter().enumerate().map(|(i, b)| b.bid * (i + 1)).sum();
-    println!("Result: {}", result);
+    winnings(&data)
 }
 
-#[test]
-fn task2() {
+fn part2(text: &str) -> Res<usize> {
     type CardType = CardTypeJokers;
 
-    let mut data = open_first(&[
-        "src/day07/input.txt",
-        "src/day07/sample.txt",
-        //
-    ])
-    .unwrap()
-    .lines()
-    .map(|line| {
-        let (hand, bid) = line.split_once(' ').expect("hand-bid separation");
-        let bid = bid.parse::<usize>().unwrap();
-        let hand: Hand<CardType> = hand
-            .chars()
-            .map(|c| CardType::parse(c).expect("valid card"))
-            .collect::<Vec<_>>()
-            .try_into()
-            .expect("A valid hand of 5 cards");
-        let hand = HandType::<CardType>::parse(hand);
-        return Bid { hand, bid };
-    })
-    .collect::<Vec<_>>();
+    let mut data = text
+        .lines()
+        .map(|line| {
+            let (hand, bid) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("expected a hand and a bid, got '{line}'"))?;
+            let bid = bid
+                .parse::<usize>()
+                .map_err(|e| format!("invalid bid '{bid}': {e}"))?;
+            let hand = HandType::<CardType>::parse(cards(hand, CardType::parse)?);
+            Ok(Bid { hand, bid })
+        })
+        .collect::<Res<Vec<_>>>()?;
 
     // println!("{:#?}", data);
 
@@ -241,6 +372,31 @@ fn task2() {
 
     // println!("{:#?}", data);
 
-    let result: usize = data.iter().enumerate().map(|(i, b)| b.bid * (i + 1)).sum();
+    winnings(&data)
+}
+
+#[test]
+fn task1() {
+    let text = open_first(&[
+        "src/day07/input.txt",
+        "src/day07/sample.txt",
+        //
+    ])
+    .unwrap();
+
+    let result = part1(&text).unwrap();
+    println!("Result: {}", result);
+}
+
+#[test]
+fn task2() {
+    let text = open_first(&[
+        "src/day07/input.txt",
+        "src/day07/sample.txt",
+        //
+    ])
+    .unwrap();
+
+    let result = part2(&text).unwrap();
     println!("Result: {}", result);
 }
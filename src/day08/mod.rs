@@ -1,6 +1,14 @@
-#![cfg(test)]
-
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::anonymise::Rng;
+use crate::checked;
+use crate::cycle::{self, Cycle};
+use crate::explore::Simulation;
+use crate::math;
+#[cfg(test)]
 use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Solution};
 
 #[derive(Debug, Clone, Copy)]
 enum Turn {
@@ -9,14 +17,14 @@ enum Turn {
 }
 
 impl Turn {
-    fn parse(line: &str) -> Vec<Self> {
+    fn parse(line: &str) -> Res<Vec<Self>> {
         line.chars()
             .map(|c| match c {
-                'L' => Turn::L,
-                'R' => Turn::R,
-                typ => panic!("unexpected turn: {typ}"),
+                'L' => Ok(Turn::L),
+                'R' => Ok(Turn::R),
+                typ => Err(format!("unexpected turn: {typ}")),
             })
-            .collect::<Vec<_>>()
+            .collect()
     }
 }
 
@@ -57,57 +65,232 @@ impl<'a> NameNodes<'a> {
         self.0[idx].n
     }
 
-    fn optimized(&self) -> Vec<Node> {
+    fn optimized(&self) -> Res<Vec<Node>> {
+        let index = |label: &str| {
+            self.index_of(label)
+                .ok_or_else(|| format!("invalid label '{label}'"))
+        };
         self.iter()
-            .map(|node| Node {
-                l: self.index_of(node.l).expect("invalid left label"),
-                r: self.index_of(node.r).expect("invalid right label"),
+            .map(|node| {
+                Ok(Node {
+                    l: index(node.l)?,
+                    r: index(node.r)?,
+                })
             })
             .collect()
     }
 
-    fn parse(lines: std::str::Lines<'a>) -> Self {
+    fn parse(lines: std::str::Lines<'a>) -> Res<Self> {
         let nodes = lines
             .map(|line| {
-                let (name, lr) = line.split_once("=").expect("name = (left, right)");
-                let (l, r) = lr.split_once(',').expect("(left, right)");
-                NameNode {
-                    n: name.trim(),
-                    l: l.trim().strip_prefix('(').expect("(left"),
-                    r: r.trim().strip_suffix(')').expect("right)"),
-                }
+                let node = || {
+                    let (name, lr) = line.split_once('=')?;
+                    let (l, r) = lr.split_once(',')?;
+                    Some(NameNode {
+                        n: name.trim(),
+                        l: l.trim().strip_prefix('(')?,
+                        r: r.trim().strip_suffix(')')?,
+                    })
+                };
+                node().ok_or_else(|| format!("expected 'name = (left, right)', got '{line}'"))
             })
+            .collect::<Res<_>>()?;
+        Ok(Self(nodes))
+    }
+}
+
+/// Turn order and nodes of the puzzle input
+fn parse(text: &str) -> Res<(Vec<Turn>, NameNodes<'_>)> {
+    let mut lines = text.lines();
+    let turns = Turn::parse(lines.next().unwrap_or_default())?;
+    if turns.is_empty() {
+        return Err("no turn order".to_string());
+    }
+    if lines.next() != Some("") {
+        return Err("expected an empty line before the nodes".to_string());
+    }
+    Ok((turns, NameNodes::parse(lines)?))
+}
+
+struct Network {
+    turns: Vec<Turn>,
+    nodes: Vec<Node>,
+    names: Vec<String>,
+}
+
+/// Node walk of every `A` node at once, taking a single turn per step
+#[derive(Clone)]
+pub(crate) struct Walk {
+    network: Rc<Network>,
+    current: Vec<usize>,
+    steps: usize,
+}
+
+impl Walk {
+    pub(crate) fn parse(text: &str) -> Res<Self> {
+        let (turns, named) = parse(text)?;
+        let current = named
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, node)| node.n.ends_with('A').then_some(idx))
             .collect();
-        Self(nodes)
+        let network = Network {
+            turns,
+            nodes: named.optimized()?,
+            names: named.iter().map(|node| node.n.to_string()).collect(),
+        };
+        Ok(Self {
+            network: Rc::new(network),
+            current,
+            steps: 0,
+        })
+    }
+
+    fn at_z(&self) -> impl Iterator<Item = bool> + '_ {
+        self.current
+            .iter()
+            .map(|&idx| self.network.names[idx].ends_with('Z'))
     }
 }
 
-#[test]
-fn test1() {
-    let text = open_first(&[
-        "src/day08/input.txt",
-        "src/day08/sample2.txt",
-        "src/day08/sample1.txt",
-    ])
-    .unwrap();
+impl Simulation for Walk {
+    fn step(&mut self) -> bool {
+        if self.at_z().all(|z| z) {
+            return false;
+        }
+        let turns = &self.network.turns;
+        let turn = turns[self.steps % turns.len()];
+        for current in &mut self.current {
+            *current = self.network.nodes[*current].get_node(turn);
+        }
+        self.steps += 1;
+        true
+    }
 
-    // println!("{}", text);
+    fn conditions(&self) -> &'static [&'static str] {
+        &["any-z", "all-z", "turns"]
+    }
 
-    let mut lines = text.lines();
+    fn check(&self, name: &str) -> Option<bool> {
+        match name {
+            "any-z" => Some(self.at_z().any(|z| z)),
+            "all-z" => Some(self.at_z().all(|z| z)),
+            "turns" => Some(self.steps.is_multiple_of(self.network.turns.len())),
+            _ => None,
+        }
+    }
+}
 
-    let turns = Turn::parse(lines.next().expect("no turn order"));
+impl std::fmt::Display for Walk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let turns = &self.network.turns;
+        let next = self.steps % turns.len();
+        let window = next.saturating_sub(20)..usize::min(next + 40, turns.len());
+        for (idx, turn) in turns[window.clone()].iter().enumerate() {
+            if window.start + idx == next {
+                write!(f, "[{turn:?}]")?;
+            } else {
+                write!(f, "{turn:?}")?;
+            }
+        }
+        writeln!(f)?;
+        for &idx in &self.current {
+            let node = &self.network.nodes[idx];
+            let names = &self.network.names;
+            writeln!(f, "{} = ({}, {})", names[idx], names[node.l], names[node.r])?;
+        }
+        write!(f, "steps: {}", self.steps)
+    }
+}
 
-    // println!("Turns: {:?}", turns);
+/// Rename every node, keeping `AAA`, `ZZZ` and the `A`/`Z` suffixes,
+/// and at random mirror the turns together with the left/right of each node
+pub(crate) fn anonymise(text: &str, rng: &mut Rng) -> Res<String> {
+    let (turns, named) = parse(text)?;
+    // Every label must name a node
+    named.optimized()?;
+    let mirror = rng.below(2) == 1;
+
+    let mut taken = HashSet::from(["AAA".to_string(), "ZZZ".to_string()]);
+    let mut names = HashMap::new();
+    for node in named.iter() {
+        let name = match node.n {
+            "AAA" | "ZZZ" => node.n.to_string(),
+            old => {
+                let letter = |rng: &mut Rng, from: u8, count: usize| {
+                    char::from(from + rng.below(count) as u8)
+                };
+                let mut fresh = || {
+                    let mut name = (1..old.len())
+                        .map(|_| letter(rng, b'A', 26))
+                        .collect::<String>();
+                    name.push(match old.chars().last() {
+                        Some(suffix @ ('A' | 'Z')) => suffix,
+                        _ => letter(rng, b'B', 24),
+                    });
+                    name
+                };
+                // Short names run out, so give up after a while
+                (0..1000)
+                    .map(|_| fresh())
+                    .find(|name| taken.insert(name.clone()))
+                    .ok_or_else(|| format!("no free name for node '{old}'"))?
+            }
+        };
+        names.insert(node.n, name);
+    }
 
-    assert_eq!(lines.next(), Some(""), "empty line before nodes");
+    let mut out = turns
+        .iter()
+        .map(|turn| match (turn, mirror) {
+            (Turn::L, false) | (Turn::R, true) => 'L',
+            (Turn::R, false) | (Turn::L, true) => 'R',
+        })
+        .collect::<String>();
+    out += "\n\n";
 
-    let named = NameNodes::parse(lines);
+    let mut nodes = named
+        .iter()
+        .map(|node| {
+            let (l, r) = if mirror {
+                (node.r, node.l)
+            } else {
+                (node.l, node.r)
+            };
+            format!("{} = ({}, {})\n", names[node.n], names[l], names[r])
+        })
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut nodes);
+    out.extend(nodes);
+    Ok(out)
+}
+
+pub(crate) struct Day08;
+
+impl Solution for Day08 {
+    fn day(&self) -> u8 {
+        8
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        match part {
+            1 => Ok(part1(input)?.into()),
+            2 => Ok(part2(input)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+fn part1(text: &str) -> Res<usize> {
+    let (turns, named) = parse(text)?;
+
+    // println!("Turns: {:?}", turns);
     // println!("Named: {:#?}", named);
 
-    let start = named.index_of("AAA").expect("no start node");
-    let target = named.index_of("ZZZ").expect("no target node");
+    let start = named.index_of("AAA").ok_or("no start node")?;
+    let target = named.index_of("ZZZ").ok_or("no target node")?;
 
-    let indexed = named.optimized();
+    let indexed = named.optimized()?;
 
     // println!("Indexed: {:?}", indexed);
 
@@ -120,96 +303,119 @@ fn test1() {
         }
     }
 
-    let result = count * turns.len();
-    println!("Result: {}", result);
+    checked::mul(count, turns.len(), "count * turns")
 }
 
-#[test]
-fn test2() {
-    let text = open_first(&[
-        "src/day08/input.txt", //
-        "src/day08/sample3.txt",
-    ])
-    .unwrap();
-
-    // println!("{}", text);
-
-    let mut lines = text.lines();
-
-    let turns = Turn::parse(lines.next().expect("no turn order"));
+fn part2(text: &str) -> Res<usize> {
+    let (turns, named) = parse(text)?;
 
     // println!("Turns: {:?}", turns);
-
-    assert_eq!(lines.next(), Some(""), "empty line before nodes");
-
-    let named = NameNodes::parse(lines);
     // println!("Named: {:#?}", named);
 
-    let indexed = named.optimized();
+    let indexed = named.optimized()?;
 
     // println!("Indexed: {:?}", indexed);
 
+    // Z hits at the end of a pass, `first + k * length` passes for any k >= 0
     #[derive(Debug)]
-    struct Cycle {
-        suffix: usize,
+    struct Hits {
+        first: usize,
         length: usize,
     }
 
-    impl Cycle {
-        fn hit(&self, target: usize) -> bool {
-            (target - self.suffix) % self.length == 0
-        }
-    }
-
-    let mut cycles = named
+    let hits = named
         .iter()
         .enumerate()
         .filter_map(|(idx, node)| node.n.ends_with('A').then_some(idx))
-        .map(|mut current| {
-            let mut visited: Vec<usize> = Vec::<usize>::new();
-            while !visited.contains(&current) {
-                visited.push(current);
-                for &turn in &turns {
-                    current = indexed[current].get_node(turn);
-                }
-            }
+        .map(|start| {
+            // Node at the start of every pass over the turns
+            let (Cycle { prefix, length }, visited) = cycle::history(start, |&node| {
+                turns
+                    .iter()
+                    .fold(node, |node, &turn| indexed[node].get_node(turn))
+            });
 
-            let suffix = visited.iter().position(|idx| *idx == current).unwrap();
-            let length = visited.len() - suffix;
             let target = visited
                 .iter()
                 .enumerate()
                 .filter_map(|(idx, node)| named.name(*node).ends_with('Z').then_some(idx))
                 .collect::<Vec<_>>();
-            assert_eq!(target.len(), 1, "expected a single endpoint");
-
-            // Make the first and only target be
-            // at the end of the suffix,
-            // so that a cycle always has the target at [0]
-            let suffix = target[0] + suffix - 1;
+            let start = named.name(start);
+            let [first] = target[..] else {
+                return Err(format!(
+                    "expected a single endpoint from {start}, found {}",
+                    target.len()
+                ));
+            };
+            if first < prefix {
+                return Err(format!(
+                    "expected the endpoint from {start} inside the cycle"
+                ));
+            }
 
-            return Cycle { suffix, length };
+            Ok(Hits { first, length })
         })
-        .collect::<Vec<_>>();
+        .collect::<Res<Vec<_>>>()?;
+
+    // println!("Hits: {:#?}", hits);
+
+    // Every ghost at its endpoint: count ≡ first (mod length), and no earlier than any first hit
+    let (count, period) = math::crt(
+        hits.iter()
+            .map(|hit| (hit.first as i128, hit.length as i128)),
+    )?;
+    let first = hits.iter().map(|hit| hit.first).max().unwrap_or(0) as i128;
+    let behind = (first - count).max(0) as u128;
+    let periods = behind.div_ceil(period as u128) as i128;
+    let count = checked::add(count, checked::mul(periods, period, "passes")?, "passes")?;
+    let count = usize::try_from(count).map_err(|_| format!("{count} passes overflow"))?;
+
+    checked::mul(count, turns.len(), "count * turns")
+}
 
-    cycles.sort_by_key(|cycle| cycle.length);
-    let cycles = cycles;
+#[test]
+fn test1() {
+    let text = open_first(&[
+        "src/day08/input.txt",
+        "src/day08/sample2.txt",
+        "src/day08/sample1.txt",
+    ])
+    .unwrap();
 
-    println!("Cycles: {:#?}", cycles);
+    // println!("{}", text);
 
-    // Should have used lcm (least-common-multiple) to reduce the cycle lengths (?)
-    // as that's what this is effectively doing after observing suffix and length being equal
-    let long = cycles.iter().max_by_key(|cycle| cycle.length).unwrap();
-    let mut count = long.suffix;
-    while let Some(_) = cycles.iter().find(|cycle| cycle.suffix > count) {
-        count += long.length;
-    }
-    while let Some(_) = cycles.iter().find(|cycle| !cycle.hit(count)) {
-        count += long.length;
-    }
-    // 10151663816849
-    //    36648605837 lcm is too small ?
+    let result = part1(&text).unwrap();
+    println!("Result: {}", result);
+}
 
-    let result = count * turns.len();
+#[test]
+fn test2() {
+    let text = open_first(&[
+        "src/day08/input.txt", //
+        "src/day08/sample3.txt",
+    ])
+    .unwrap();
+
+    // println!("{}", text);
+
+    let result = part2(&text).unwrap();
     println!("Result: {}", result);
 }
+
+#[test]
+fn walk() {
+    // The explorer walks every ghost by hand, it must agree with the CRT
+    let text = open_first(&["src/day08/sample3.txt"]).unwrap();
+    let mut walk = Walk::parse(&text).unwrap();
+    while walk.step() {}
+    assert_eq!(walk.check("all-z"), Some(true));
+    assert_eq!(Ok(walk.steps), part2(&text));
+    assert!(Walk::parse("LR\n\nAAA = (BBB, CCC)\n").is_err());
+    assert!(Walk::parse("LX\n\nAAA = (AAA, AAA)\n").is_err());
+
+    // Shapes the cycle and CRT approach does not cover
+    let two_ends = "L\n\nAAA = (BBZ, BBZ)\nBBZ = (CCZ, CCZ)\nCCZ = (AAA, AAA)\n";
+    assert!(part2(two_ends).is_err());
+    let end_before_cycle = "L\n\nAAA = (BBZ, BBZ)\nBBZ = (CCC, CCC)\nCCC = (CCC, CCC)\n";
+    assert!(part2(end_before_cycle).is_err());
+}
@@ -1,36 +1,114 @@
-#![cfg(test)]
-
+use crate::bigint::{BigInt, Number};
+use crate::checked;
+#[cfg(test)]
 use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Settings, Solution};
 
-type Number = isize;
 struct Extrapolate;
 
 impl Extrapolate {
-    fn some(seq: &Vec<Number>) -> bool {
-        seq.windows(2).find(|s| s[1] != s[0]).is_some()
+    fn some<N: Number>(seq: &[N]) -> bool {
+        seq.windows(2).any(|s| s[1] != s[0])
+    }
+
+    fn next<N: Number>(seq: &[N]) -> Res<Vec<N>> {
+        seq.windows(2)
+            .map(|s| checked::sub(s[1].clone(), s[0].clone(), "difference"))
+            .collect()
     }
 
-    fn next(seq: &Vec<Number>) -> Vec<Number> {
-        seq.windows(2).map(|s| s[1] - s[0]).collect()
+    /// The sequence and its differences, down to a constant one
+    fn parse<N: Number>(line: &str) -> Res<Vec<Vec<N>>> {
+        let sequence = line
+            .split_whitespace()
+            .map(|val| {
+                let num = val
+                    .parse()
+                    .map_err(|e| format!("invalid value '{val}': {e}"))?;
+                N::from_i64(num).ok_or_else(|| format!("{num} does not fit {}", N::NAME))
+            })
+            .collect::<Res<Vec<_>>>()?;
+        if sequence.is_empty() {
+            return Err("empty sequence".to_string());
+        }
+
+        let mut sequences = vec![sequence];
+        while let Some(last) = sequences.last().filter(|seq| Self::some(seq)) {
+            let next = Self::next(last)?;
+            sequences.push(next);
+        }
+        Ok(sequences)
     }
 
-    fn forward(set: &mut Vec<Vec<Number>>) -> Number {
-        set.iter_mut().rfold(0, |diff, seq| -> Number {
-            let next = seq.last().expect("missing last element") + diff;
-            seq.push(next);
-            return next;
+    fn forward<N: Number>(set: &mut [Vec<N>]) -> Res<N> {
+        set.iter_mut().try_rfold(N::zero(), |diff, seq| {
+            let next = checked::add(seq[seq.len() - 1].clone(), diff, "next value")?;
+            seq.push(next.clone());
+            Ok(next)
         })
     }
 
-    fn reverse(set: &mut Vec<Vec<Number>>) -> Number {
-        set.iter_mut().rfold(0, |diff, seq| -> Number {
-            let prev = seq.first().expect("missing first element") - diff;
-            seq.insert(0, prev);
-            return prev;
+    fn reverse<N: Number>(set: &mut [Vec<N>]) -> Res<N> {
+        set.iter_mut().try_rfold(N::zero(), |diff, seq| {
+            let prev = checked::sub(seq[0].clone(), diff, "previous value")?;
+            seq.insert(0, prev.clone());
+            Ok(prev)
         })
     }
 }
 
+/// `number` extrapolates with machine or big integers
+const SETTINGS: &[&str] = &["number"];
+
+pub(crate) struct Day09;
+
+impl Solution for Day09 {
+    fn day(&self) -> u8 {
+        9
+    }
+
+    fn settings(&self) -> &'static [&'static str] {
+        SETTINGS
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        self.solve_with(part, input, &Settings::default())
+    }
+
+    fn solve_with(&self, part: u8, input: &str, settings: &Settings) -> Res<Answer> {
+        settings.check(self.day(), SETTINGS)?;
+        match (part, settings.big()?) {
+            (1, false) => Ok(part1::<i64>(input)?.into()),
+            (1, true) => Ok(part1::<BigInt>(input)?.into()),
+            (2, false) => Ok(part2::<i64>(input)?.into()),
+            (2, true) => Ok(part2::<BigInt>(input)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+fn part1<N: Number>(text: &str) -> Res<N> {
+    let results = text.lines().map(|line| {
+        let mut sequences = Extrapolate::parse(line)?;
+        let result = Extrapolate::forward(&mut sequences);
+        // println!("{:?} -> {:?}", sequences, result);
+
+        result
+    });
+    checked::sum(results, "sum of next values")
+}
+
+fn part2<N: Number>(text: &str) -> Res<N> {
+    let results = text.lines().map(|line| {
+        let mut sequences = Extrapolate::parse(line)?;
+        let result = Extrapolate::reverse(&mut sequences);
+        // println!("{:?} -> {:?}", sequences, result);
+
+        result
+    });
+    checked::sum(results, "sum of previous values")
+}
+
 #[test]
 fn task1() {
     let text = open_first(&[
@@ -39,26 +117,11 @@ fn task1() {
     ])
     .unwrap();
 
-    let result: Number = text
-        .lines()
-        .map(|line| {
-            let sequence = line
-                .split_whitespace()
-                .map(|val| val.parse::<Number>().expect("not a number"))
-                .collect::<Vec<_>>();
-
-            let mut sequences = vec![sequence];
-            while Extrapolate::some(sequences.last().unwrap()) {
-                sequences.push(Extrapolate::next(sequences.last().unwrap()));
-            }
-
-            let result = Extrapolate::forward(&mut sequences);
-            // println!("{:?} -> {}", sequences, result);
-
-            result
-        })
-        .sum();
-
+    let result = part1::<i64>(&text).unwrap();
+    assert_eq!(
+        part1::<BigInt>(&text).unwrap().to_string(),
+        result.to_string()
+    );
     println!("Result: {}", result);
 }
 
@@ -70,25 +133,10 @@ fn task2() {
     ])
     .unwrap();
 
-    let result: Number = text
-        .lines()
-        .map(|line| {
-            let sequence = line
-                .split_whitespace()
-                .map(|val| val.parse::<Number>().expect("not a number"))
-                .collect::<Vec<_>>();
-
-            let mut sequences = vec![sequence];
-            while Extrapolate::some(sequences.last().unwrap()) {
-                sequences.push(Extrapolate::next(sequences.last().unwrap()));
-            }
-
-            let result = Extrapolate::reverse(&mut sequences);
-            // println!("{:?} -> {}", sequences, result);
-
-            result
-        })
-        .sum();
-
+    let result = part2::<i64>(&text).unwrap();
+    assert_eq!(
+        part2::<BigInt>(&text).unwrap().to_string(),
+        result.to_string()
+    );
     println!("Result: {}", result);
 }
@@ -1,6 +1,13 @@
-#![cfg(test)]
+use std::rc::Rc;
 
+use crate::coord::{Dir4, Point, Pos, Step};
+use crate::explore::Simulation;
+use crate::geometry;
+use crate::grid::Grid;
+#[cfg(test)]
 use crate::open_first;
+use crate::search;
+use crate::solution::{missing_part, Answer, Res, Solution};
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum Pipe {
@@ -29,6 +36,29 @@ impl Pipe {
         })
     }
 
+    /// The two sides the pipe opens to
+    fn exits(self) -> Option<[Dir4; 2]> {
+        match self {
+            Self::Vertical => Some([Dir4::N, Dir4::S]),
+            Self::Horisontal => Some([Dir4::W, Dir4::E]),
+            Self::NorthEast => Some([Dir4::N, Dir4::E]),
+            Self::NorthWest => Some([Dir4::N, Dir4::W]),
+            Self::SouthWest => Some([Dir4::S, Dir4::W]),
+            Self::SouthEast => Some([Dir4::S, Dir4::E]),
+            Self::Ground | Self::Creature => None,
+        }
+    }
+
+    /// Heading out of the pipe after entering it heading `dir`
+    fn next(self, dir: Dir4) -> Option<Dir4> {
+        let [a, b] = self.exits()?;
+        match dir.opposite() {
+            from if from == a => Some(b),
+            from if from == b => Some(a),
+            _ => None,
+        }
+    }
+
     fn symbol(&self) -> &'static str {
         match self {
             Self::Vertical => "║ ",
@@ -49,256 +79,222 @@ impl std::fmt::Debug for Pipe {
     }
 }
 
-#[derive(Debug)]
-struct Pos {
-    x: usize,
-    y: usize,
-}
-
-struct PipeGrid {
-    width: usize,
-    pipes: Vec<Pipe>,
-}
-
-impl PipeGrid {
-    fn parse(text: &str) -> Option<Self> {
-        let width = text.lines().next()?.len();
-        let pipes = text
-            .lines()
-            .flat_map(|line| line.chars())
-            .map(Pipe::parse)
-            .collect::<Option<Vec<_>>>()?;
-        Some(Self { width, pipes })
-    }
-    fn cols(&self) -> usize {
-        self.width
-    }
-    fn rows(&self) -> usize {
-        self.pipes.len() / self.width
+impl Grid<Pipe> {
+    fn creature(&self) -> Option<Pos> {
+        self.position(|&pipe| pipe == Pipe::Creature)
     }
-    fn at(&self, x: usize, y: usize) -> Pipe {
-        self.pipes[x + self.width * y]
+    /// Tiles joined to `pos` by its pipe, the creature joins the two pipes facing it
+    fn connected(&self, pos: &Pos) -> Vec<Pos> {
+        let here = self[*pos];
+        let exits = match here.exits() {
+            Some(exits) => exits.to_vec(),
+            None if here == Pipe::Creature => self
+                .starts()
+                .map_or(Vec::new(), |steps| steps.map(|step| step.dir).to_vec()),
+            None => Vec::new(),
+        };
+        exits
+            .into_iter()
+            .filter_map(|dir| {
+                let next = pos.checked_step(dir, self.bounds())?;
+                let there = self[next];
+                (there == Pipe::Creature || there.next(dir).is_some()).then_some(next)
+            })
+            .collect()
     }
-    fn creature(&self) -> Option<Pos> {
-        self.pipes
-            .iter()
-            .position(|&pipe| pipe == Pipe::Creature)
-            .map(|pos| Pos {
-                x: pos % self.width,
-                y: pos / self.width,
+    /// First steps out of the creature, into the two pipes facing it
+    fn starts(&self) -> Res<[Step; 2]> {
+        let pos = self.creature().ok_or("no creature")?;
+        let steps = Dir4::ALL
+            .into_iter()
+            .filter_map(|dir| {
+                let step = Step { pos, dir }.checked_step(dir, self.bounds())?;
+                self[step.pos].next(dir).map(|_| step)
             })
+            .collect::<Vec<_>>();
+        <[Step; 2]>::try_from(steps).map_err(|steps| {
+            format!(
+                "expected 2 pipes facing the creature, found {}",
+                steps.len()
+            )
+        })
+    }
+    /// Take the pipe at `step` to the next tile
+    fn follow(&self, step: Step) -> Res<Step> {
+        let pos = step.pos;
+        let dir = self[pos]
+            .next(step.dir)
+            .ok_or_else(|| format!("loop broken at {pos:?}, no pipe going {:?}", step.dir))?;
+        step.checked_step(dir, self.bounds())
+            .ok_or_else(|| format!("loop leaves the grid at {pos:?}"))
     }
     fn repr(&self) -> String {
+        self.repr_marked(&[])
+    }
+    fn repr_marked(&self, marks: &[(Pos, &str)]) -> String {
         let rows = self.rows() + 2;
         let cols = self.cols() + 1;
         let mut out = String::with_capacity(rows * cols * 2 + rows);
         out.extend((0..cols).map(|_| "# "));
-        for line in self.pipes.chunks_exact(self.width) {
+        for (y, line) in self.lines().enumerate() {
             out += "#\n# ";
-            out.extend(line.iter().map(Pipe::symbol));
+            out.extend(line.iter().enumerate().map(|(x, pipe)| {
+                marks
+                    .iter()
+                    .find(|mark| mark.0 == Pos::new(x, y))
+                    .map_or(pipe.symbol(), |mark| mark.1)
+            }));
         }
         out += "#\n";
         out.extend((0..cols).map(|_| "# "));
         out += "#";
-        return out;
+        out
     }
 }
 
-impl std::fmt::Display for PipeGrid {
+impl std::fmt::Display for Grid<Pipe> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.repr())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Direction {
-    N,
-    S,
-    W,
-    E,
+/// The two walkers of task1, leaving the creature in opposite directions
+#[derive(Clone)]
+pub(crate) struct Walkers {
+    grid: Rc<Grid<Pipe>>,
+    s1: Step,
+    s2: Step,
+    count: usize,
+    /// Why a walker had to stop before they met
+    broken: Option<String>,
 }
 
-impl Direction {
-    const fn all() -> [Self; 4] {
-        [Direction::N, Direction::S, Direction::W, Direction::E]
+impl Walkers {
+    pub(crate) fn parse(text: &str) -> Res<Self> {
+        let grid = Grid::parse(text, Pipe::parse).ok_or("invalid grid")?;
+        let [s1, s2] = grid.starts()?;
+        Ok(Self {
+            grid: Rc::new(grid),
+            s1,
+            s2,
+            count: 1,
+            broken: None,
+        })
+    }
+
+    fn met(&self) -> bool {
+        self.s1.pos == self.s2.pos
     }
-    fn next(self, pipe: Pipe) -> Option<Self> {
-        match (self, pipe) {
-            // N
-            (Self::N, Pipe::Vertical) => Some(Self::N),
-            (Self::N, Pipe::Horisontal) => None,
-            (Self::N, Pipe::NorthEast) => None,
-            (Self::N, Pipe::NorthWest) => None,
-            (Self::N, Pipe::SouthWest) => Some(Self::W),
-            (Self::N, Pipe::SouthEast) => Some(Self::E),
-            // S
-            (Self::S, Pipe::Vertical) => Some(Self::S),
-            (Self::S, Pipe::Horisontal) => None,
-            (Self::S, Pipe::NorthEast) => Some(Self::E),
-            (Self::S, Pipe::NorthWest) => Some(Self::W),
-            (Self::S, Pipe::SouthWest) => None,
-            (Self::S, Pipe::SouthEast) => None,
-            // W
-            (Self::W, Pipe::Vertical) => None,
-            (Self::W, Pipe::Horisontal) => Some(Self::W),
-            (Self::W, Pipe::NorthEast) => Some(Self::N),
-            (Self::W, Pipe::NorthWest) => None,
-            (Self::W, Pipe::SouthWest) => None,
-            (Self::W, Pipe::SouthEast) => Some(Self::S),
-            // E
-            (Self::E, Pipe::Vertical) => None,
-            (Self::E, Pipe::Horisontal) => Some(Self::E),
-            (Self::E, Pipe::NorthEast) => None,
-            (Self::E, Pipe::NorthWest) => Some(Self::N),
-            (Self::E, Pipe::SouthWest) => Some(Self::S),
-            (Self::E, Pipe::SouthEast) => None,
-            // Rest
-            (_, Pipe::Ground) => None,
-            (_, Pipe::Creature) => {
-                println!("[WARN]: ontop of creature");
-                None
+}
+
+impl Simulation for Walkers {
+    fn step(&mut self) -> bool {
+        if self.met() || self.broken.is_some() {
+            return false;
+        }
+        let next = self
+            .grid
+            .follow(self.s1)
+            .and_then(|s1| Ok((s1, self.grid.follow(self.s2)?)));
+        match next {
+            Ok((s1, s2)) => {
+                (self.s1, self.s2) = (s1, s2);
+                self.count += 1;
+                true
+            }
+            Err(err) => {
+                self.broken = Some(err);
+                false
             }
         }
     }
-}
 
-#[derive(Clone, Copy)]
-struct Step {
-    x: usize,
-    y: usize,
-    d: Direction,
-}
+    fn conditions(&self) -> &'static [&'static str] {
+        &["met"]
+    }
 
-impl PartialEq for Step {
-    fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
+    fn check(&self, name: &str) -> Option<bool> {
+        (name == "met").then(|| self.met())
     }
 }
 
-impl Pos {
-    fn checked_step(&self, dir: Direction, w: usize, h: usize) -> Option<Step> {
-        match dir {
-            Direction::N => (self.y > 0).then(|| Step {
-                x: self.x,
-                y: self.y - 1,
-                d: dir,
-            }),
-            Direction::S => (self.y + 1 < h).then(|| Step {
-                x: self.x,
-                y: self.y + 1,
-                d: dir,
-            }),
-            Direction::W => (self.x > 0).then(|| Step {
-                x: self.x - 1,
-                y: self.y,
-                d: dir,
-            }),
-            Direction::E => (self.x + 1 < w).then(|| Step {
-                x: self.x + 1,
-                y: self.y,
-                d: dir,
-            }),
+impl std::fmt::Display for Walkers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let marks = [(self.s1.pos, "1 "), (self.s2.pos, "2 ")];
+        writeln!(f, "{}", self.grid.repr_marked(&marks))?;
+        write!(f, "distance: {}", self.count)?;
+        match &self.broken {
+            Some(err) => write!(f, "\n{err}"),
+            None => Ok(()),
         }
     }
 }
 
-impl Step {
-    fn step(&self, dir: Direction) -> Self {
-        match dir {
-            Direction::N => Self {
-                x: self.x,
-                y: self.y - 1,
-                d: dir,
-            },
-            Direction::S => Self {
-                x: self.x,
-                y: self.y + 1,
-                d: dir,
-            },
-            Direction::W => Self {
-                x: self.x - 1,
-                y: self.y,
-                d: dir,
-            },
-            Direction::E => Self {
-                x: self.x + 1,
-                y: self.y,
-                d: dir,
-            },
+pub(crate) struct Day10;
+
+impl Solution for Day10 {
+    fn day(&self) -> u8 {
+        10
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        match part {
+            1 => Ok(part1(input)?.into()),
+            2 => Ok(part2(input)?.into()),
+            _ => missing_part(self.day(), part),
         }
     }
 }
 
-#[test]
-fn task1() {
-    let text = open_first(&[
-        "src/day10/input.txt",   //
-        "src/day10/sample2.txt", //
-        "src/day10/sample1.txt", //
-    ])
-    .unwrap();
+fn part1(text: &str) -> Res<usize> {
+    let grid = Grid::parse(text, Pipe::parse).ok_or("invalid grid")?;
+    let pos = grid.creature().ok_or("no creature")?;
+    // Only the loop may lead out of the creature
+    grid.starts()?;
+    let found = search::bfs(pos, |pos| grid.connected(pos), |_| false);
+    // The farthest tile is half way round the loop
+    Ok(found.distances().values().copied().max().unwrap_or(0))
+}
 
-    let grid = PipeGrid::parse(&text).expect("invalid grid");
-    // println!("{}", grid);
-    let pos = grid.creature().expect("no creature");
-    println!("Creature {:?}", pos);
+/// Tiles of the loop in walking order, starting at the creature
+fn pipe_loop(grid: &Grid<Pipe>) -> Res<Vec<Pos>> {
+    let start = grid.creature().ok_or("no creature")?;
+    let [first, _] = grid.starts()?;
+    let mut tiles = vec![start];
+    let mut prev = start;
+    let mut pos = first.pos;
+    while pos != start {
+        let next = grid
+            .connected(&pos)
+            .into_iter()
+            .find(|next| *next != prev)
+            .ok_or("pipe loop is broken")?;
+        tiles.push(pos);
+        (prev, pos) = (pos, next);
+    }
+    Ok(tiles)
+}
 
-    let init = Direction::all()
+/// Enclosed tiles by Pick's theorem, with the tile centres of the loop as polygon
+fn part2(text: &str) -> Res<usize> {
+    let grid = Grid::parse(text, Pipe::parse).ok_or("invalid grid")?;
+    let poly = pipe_loop(&grid)?
         .into_iter()
-        .filter_map(|dir| {
-            let step = pos.checked_step(dir, grid.rows(), grid.cols())?;
-            let pipe = grid.at(step.x, step.y);
-            dir.next(pipe).map(|_| step)
-        })
+        .map(Point::from)
         .collect::<Vec<_>>();
-    assert_eq!(init.len(), 2, "expected exactly 2 paths");
-    let mut s1 = init[0];
-    let mut s2 = init[1];
-
-    let next = |s: &mut Step| {
-        let pipe = grid.at(s.x, s.y);
-        let dir = s.d.next(pipe).expect("invalid path");
-        *s = s.step(dir);
-    };
-
-    let mut count = 1;
-    while s1 != s2 {
-        count += 1;
-        next(&mut s1);
-        next(&mut s2);
-    }
-
-    println!("Result: {}", count);
+    let inside = geometry::interior_points(&poly)?;
+    usize::try_from(inside).map_err(|_| format!("negative count of enclosed tiles: {inside}"))
 }
 
-#[test]
-fn task2() {
-    let text = open_first(&[
-        "src/day10/input.txt",   //
-        "src/day10/sample4.txt", //
-        "src/day10/sample3.txt", //
-        "src/day10/sample1.txt", //
-        "src/day10/sample2.txt", //
-    ])
-    .unwrap();
-
-    let grid = PipeGrid::parse(&text).expect("invalid grid");
+/// Enclosed tiles by scanning each row for loop crossings
+#[cfg(test)]
+fn part2_scan(text: &str) -> usize {
+    let grid = Grid::parse(text, Pipe::parse).expect("invalid grid");
     // println!("{}", grid);
     let pos = grid.creature().expect("no creature");
-    println!("Creature {:?}", pos);
+    // println!("Creature {:?}", pos);
 
-    let init = Direction::all()
-        .into_iter()
-        .filter_map(|dir| {
-            let step = pos.checked_step(dir, grid.cols(), grid.rows())?;
-            let pipe = grid.at(step.x, step.y);
-            dir.next(pipe)?;
-            Some(step)
-        })
-        .collect::<Vec<_>>();
-    assert_eq!(init.len(), 2, "expected exactly 2 paths");
-    let mut s1 = init[0];
-    let mut s2 = init[1];
+    let [mut s1, mut s2] = grid.starts().unwrap();
 
     #[derive(PartialEq, Eq)]
     enum PathType {
@@ -309,31 +305,28 @@ fn task2() {
         None,
     }
 
-    let mut path = grid
-        .pipes
-        .iter()
+    let mut path = (0..grid.cols() * grid.rows())
         .map(|_| PathType::None)
         .collect::<Vec<_>>();
 
-    path[pos.x + grid.width * pos.y] = match (s1.d, s2.d) {
-        (Direction::N, Direction::S) => PathType::Wall,
-        (Direction::N, Direction::W) => PathType::Up,
-        (Direction::N, Direction::E) => PathType::Up,
-        (Direction::S, Direction::N) => PathType::Wall,
-        (Direction::S, Direction::W) => PathType::Dn,
-        (Direction::S, Direction::E) => PathType::Dn,
-        (Direction::W, Direction::N) => PathType::Up,
-        (Direction::W, Direction::S) => PathType::Dn,
-        (Direction::W, Direction::E) => PathType::Cont,
-        (Direction::E, Direction::N) => PathType::Up,
-        (Direction::E, Direction::S) => PathType::Dn,
-        (Direction::E, Direction::W) => PathType::Cont,
+    path[pos.index(grid.cols())] = match (s1.dir, s2.dir) {
+        (Dir4::N, Dir4::S) => PathType::Wall,
+        (Dir4::N, Dir4::W) => PathType::Up,
+        (Dir4::N, Dir4::E) => PathType::Up,
+        (Dir4::S, Dir4::N) => PathType::Wall,
+        (Dir4::S, Dir4::W) => PathType::Dn,
+        (Dir4::S, Dir4::E) => PathType::Dn,
+        (Dir4::W, Dir4::N) => PathType::Up,
+        (Dir4::W, Dir4::S) => PathType::Dn,
+        (Dir4::W, Dir4::E) => PathType::Cont,
+        (Dir4::E, Dir4::N) => PathType::Up,
+        (Dir4::E, Dir4::S) => PathType::Dn,
+        (Dir4::E, Dir4::W) => PathType::Cont,
         _ => panic!("unexpected equal direction"),
     };
 
     let mut next = |s: &mut Step| {
-        let pipe = grid.at(s.x, s.y);
-        path[s.x + grid.width * s.y] = match pipe {
+        path[s.pos.index(grid.cols())] = match grid[s.pos] {
             Pipe::Vertical => PathType::Wall,
             Pipe::Horisontal => PathType::Cont,
             Pipe::NorthEast => PathType::Up,
@@ -342,11 +335,10 @@ fn task2() {
             Pipe::SouthEast => PathType::Dn,
             _ => panic!("unexpected pipe"),
         };
-        let dir = s.d.next(pipe).expect("invalid path");
-        *s = s.step(dir);
+        *s = grid.follow(*s).expect("broken loop");
     };
 
-    while s1 != s2 {
+    while s1.pos != s2.pos {
         next(&mut s1);
         next(&mut s2);
     }
@@ -354,11 +346,7 @@ fn task2() {
     // Register last path
     next(&mut s1);
 
-    // release mutable borrow of path
-    drop(next);
-
-    let count: usize = path
-        .chunks_exact(grid.width)
+    path.chunks_exact(grid.cols())
         .map(|row| -> usize {
             // for cell in row {
             //     match cell {
@@ -367,7 +355,7 @@ fn task2() {
             //         PathType::None => print!("  "),
             //     }
             // }
-            let count = (0..grid.width)
+            let count = (0..grid.cols())
                 .map(|pos| {
                     match row[pos] {
                         PathType::Up => {
@@ -414,17 +402,98 @@ fn task2() {
 
                     if inside {
                         // print!("I ");
-                        return 1;
+                        1
                     } else {
                         // print!("O ");
-                        return 0;
+                        0
                     }
                 })
                 .sum();
-            println!("");
-            return count;
+            // println!("");
+            count
         })
-        .sum();
+        .sum()
+}
+
+#[test]
+fn task1() {
+    let text = open_first(&[
+        "src/day10/input.txt",   //
+        "src/day10/sample2.txt", //
+        "src/day10/sample1.txt", //
+    ])
+    .unwrap();
+
+    let result = part1(&text).unwrap();
+    println!("Result: {}", result);
+}
+
+#[test]
+fn walkers() {
+    // The explorer walks the loop by hand, it must agree with the search
+    for n in 1..=4 {
+        let Ok(text) = open_first(&[&format!("src/day10/sample{n}.txt")]) else {
+            continue;
+        };
+        let mut walkers = Walkers::parse(&text).unwrap();
+        while walkers.step() {}
+        assert_eq!(Ok(walkers.count), part1(&text), "sample{n}");
+    }
+
+    // A loop broken half way stops the walkers instead of panicking
+    let mut walkers = Walkers::parse(".....\n.S-7.\n.|...\n.L-..\n.....\n").unwrap();
+    while walkers.step() {}
+    assert!(!walkers.met());
+    assert!(walkers.to_string().contains("loop broken"));
+}
+
+#[test]
+fn task2() {
+    let text = open_first(&[
+        "src/day10/input.txt",   //
+        "src/day10/sample4.txt", //
+        "src/day10/sample3.txt", //
+        "src/day10/sample1.txt", //
+        "src/day10/sample2.txt", //
+    ])
+    .unwrap();
+
+    let result = part2(&text).unwrap();
+    assert_eq!(result, part2_scan(&text));
+    println!("Result: {}", result);
+}
+
+#[test]
+fn search() {
+    // Farthest tile of each loop, from the puzzle text
+    for (n, farthest) in [(1, 4), (2, 8)] {
+        let text = open_first(&[&format!("src/day10/sample{n}.txt")]).unwrap();
+        let grid = Grid::parse(&text, Pipe::parse).unwrap();
+        let start = grid.creature().unwrap();
+        let found = search::bfs(start, |pos| grid.connected(pos), |_| false);
+        let (&far, &dist) = found
+            .distances()
+            .iter()
+            .max_by_key(|(_, &dist)| dist)
+            .unwrap();
+        assert_eq!(dist, farthest, "sample{n}");
+        assert_eq!(found.distances().len(), pipe_loop(&grid).unwrap().len());
+
+        let path = found.path(&far).unwrap();
+        assert_eq!((path.len(), path[0]), (farthest + 1, start));
+        assert!(path
+            .windows(2)
+            .all(|w| grid.connected(&w[0]).contains(&w[1])));
+
+        let weighted = |pos: &Pos| grid.connected(pos).into_iter().map(|next| (next, 1));
+        let dijkstra = search::dijkstra(start, weighted, |_| false);
+        assert_eq!(dijkstra.distances(), found.distances(), "sample{n}");
+    }
 
-    println!("Result: {}", count);
+    // A third pipe facing the creature leaves no single loop to follow
+    let text = open_first(&["src/day10/sample1.txt"]).unwrap();
+    let text = text.replacen("7S", "-S", 1);
+    assert!(part1(&text).is_err());
+    assert!(part2(&text).is_err());
+    assert!(Walkers::parse(&text).is_err());
 }
@@ -1,6 +1,8 @@
-#![cfg(test)]
-
+use crate::bigint::{BigInt, Number};
+use crate::checked;
+#[cfg(test)]
 use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Settings, Solution};
 
 fn order(a: usize, b: usize) -> (usize, usize) {
     if a < b {
@@ -10,14 +12,39 @@ fn order(a: usize, b: usize) -> (usize, usize) {
     }
 }
 
-#[test]
-fn task1() {
-    let text = open_first(&[
-        "src/day11/input.txt",  //
-        "src/day11/sample.txt", //
-    ])
-    .unwrap();
+/// `number` sums the distances with machine or big integers
+const SETTINGS: &[&str] = &["number"];
+
+pub(crate) struct Day11;
 
+impl Solution for Day11 {
+    fn day(&self) -> u8 {
+        11
+    }
+
+    fn settings(&self) -> &'static [&'static str] {
+        SETTINGS
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        self.solve_with(part, input, &Settings::default())
+    }
+
+    fn solve_with(&self, part: u8, input: &str, settings: &Settings) -> Res<Answer> {
+        settings.check(self.day(), SETTINGS)?;
+        match (part, settings.big()?) {
+            (1, false) => Ok(part1::<usize>(input)?.into()),
+            (1, true) => Ok(part1::<BigInt>(input)?.into()),
+            (2, false) => Ok(part2::<usize>(input)?.into()),
+            (2, true) => Ok(part2::<BigInt>(input)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+/// Sum of the distances between every pair of stars, after each empty row
+/// and column has grown to `age` of them
+fn total_distance<N: Number>(text: &str, age: N) -> Res<N> {
     // println!("{}", text);
 
     let stars = text
@@ -26,14 +53,24 @@ fn task1() {
         .flat_map(|(row, line)| {
             line.chars()
                 .enumerate()
-                .filter_map(move |(col, char)| (char == '#').then(|| (row, col)))
+                .filter(|&(_, char)| char == '#')
+                .map(move |(col, _)| (row, col))
         })
         .collect::<Vec<_>>();
 
     // println!("{:?}", stars);
 
-    let rows = stars.iter().map(|(row, _)| *row).max().unwrap() + 1;
-    let cols = stars.iter().map(|(_, col)| *col).max().unwrap() + 1;
+    // No stars means no pairs, whatever the size
+    let rows = stars
+        .iter()
+        .map(|(row, _)| *row)
+        .max()
+        .map_or(0, |row| row + 1);
+    let cols = stars
+        .iter()
+        .map(|(_, col)| *col)
+        .max()
+        .map_or(0, |col| col + 1);
 
     let mut rows = vec![true; rows];
     let mut cols = vec![true; cols];
@@ -46,87 +83,80 @@ fn task1() {
     // println!("rows: {:?}", rows);
     // println!("cols: {:?}", cols);
 
-    let result: usize = (1..stars.len())
-        .map(|idx| -> usize {
-            let (a_row, a_col) = stars[idx - 1];
-            stars[idx..]
-                .iter()
-                .map(|&(b_row, b_col)| {
-                    let (l_row, h_row) = order(a_row, b_row);
-                    let rows = rows[l_row..h_row].iter().filter(|&empty| *empty).count();
-                    let rows = rows + h_row - l_row;
-
-                    let (l_col, h_col) = order(a_col, b_col);
-                    let cols = cols[l_col..h_col].iter().filter(|&empty| *empty).count();
-                    let cols = cols + h_col - l_col;
-
-                    return rows + cols;
-                })
-                .sum()
-        })
-        .sum();
+    let age_term = checked::sub(age, N::from(1), "age")?;
+    let number = |n: usize| N::from_usize(n).ok_or_else(|| format!("{n} does not fit {}", N::NAME));
+    let distances = (1..stars.len()).map(|idx| -> Res<N> {
+        let (a_row, a_col) = stars[idx - 1];
+        checked::sum(
+            stars[idx..].iter().map(|&(b_row, b_col)| {
+                let (l_row, h_row) = order(a_row, b_row);
+                let rows = rows[l_row..h_row].iter().filter(|&empty| *empty).count();
+
+                let (l_col, h_col) = order(a_col, b_col);
+                let cols = cols[l_col..h_col].iter().filter(|&empty| *empty).count();
+
+                let empty = number(rows + cols)?;
+                let span = number(h_row - l_row + h_col - l_col)?;
+                let grown = checked::mul(empty, age_term.clone(), "grown lines")?;
+                checked::add(grown, span, "distance")
+            }),
+            "distances from one star",
+        )
+    });
+    checked::sum(distances, "total distance")
+}
 
-    println!("Result: {}", result);
+fn part1<N: Number>(text: &str) -> Res<N> {
+    total_distance(text, N::from(2))
+}
+
+fn part2<N: Number>(text: &str) -> Res<N> {
+    const UNIVERSE_AGE: usize = 1_000_000;
+    let age = N::from_usize(UNIVERSE_AGE)
+        .ok_or_else(|| format!("{UNIVERSE_AGE} does not fit {}", N::NAME))?;
+    total_distance(text, age)
 }
 
 #[test]
-fn task2() {
+fn task1() {
     let text = open_first(&[
         "src/day11/input.txt",  //
         "src/day11/sample.txt", //
     ])
     .unwrap();
 
-    const UNIVERSE_AGE: usize = 1_000_000;
-    const AGE_TERM: usize = UNIVERSE_AGE - 1;
-
-    // println!("{}", text);
-
-    let stars = text
-        .lines()
-        .enumerate()
-        .flat_map(|(row, line)| {
-            line.chars()
-                .enumerate()
-                .filter_map(move |(col, char)| (char == '#').then(|| (row, col)))
-        })
-        .collect::<Vec<_>>();
-
-    // println!("{:?}", stars);
-
-    let rows = stars.iter().map(|(row, _)| *row).max().unwrap() + 1;
-    let cols = stars.iter().map(|(_, col)| *col).max().unwrap() + 1;
-
-    let mut rows = vec![true; rows];
-    let mut cols = vec![true; cols];
-
-    for (row, col) in &stars {
-        rows[*row] = false;
-        cols[*col] = false;
-    }
-
-    // println!("rows: {:?}", rows);
-    // println!("cols: {:?}", cols);
+    let result = part1::<usize>(&text).unwrap();
+    println!("Result: {}", result);
+}
 
-    let result: usize = (1..stars.len())
-        .map(|idx| -> usize {
-            let (a_row, a_col) = stars[idx - 1];
-            stars[idx..]
-                .iter()
-                .map(|&(b_row, b_col)| {
-                    let (l_row, h_row) = order(a_row, b_row);
-                    let rows = rows[l_row..h_row].iter().filter(|&empty| *empty).count();
-                    let rows = rows * AGE_TERM + h_row - l_row;
-
-                    let (l_col, h_col) = order(a_col, b_col);
-                    let cols = cols[l_col..h_col].iter().filter(|&empty| *empty).count();
-                    let cols = cols * AGE_TERM + h_col - l_col;
-
-                    return rows + cols;
-                })
-                .sum()
-        })
-        .sum();
+#[test]
+fn task2() {
+    let text = open_first(&[
+        "src/day11/input.txt",  //
+        "src/day11/sample.txt", //
+    ])
+    .unwrap();
 
+    let result = part2::<usize>(&text).unwrap();
     println!("Result: {}", result);
 }
+
+#[test]
+fn ancient() {
+    let text = open_first(&["src/day11/sample.txt"]).unwrap();
+    // Every empty line crossed adds one less than the age
+    let (young, old) = (
+        part1::<usize>(&text).unwrap(),
+        part2::<usize>(&text).unwrap(),
+    );
+    let per_age = (old - young) / (1_000_000 - 2);
+    let age = BigInt::from(10u64.pow(19)) * BigInt::from(10);
+    let expected = BigInt::from(young) + BigInt::from(per_age) * (age.clone() - BigInt::from(2));
+    assert_eq!(total_distance(&text, age), Ok(expected));
+
+    let big = [("number", "big")].into_iter().collect::<Settings>();
+    assert_eq!(
+        Day11.solve_with(2, &text, &big),
+        Ok(Answer::Big(BigInt::from(old)))
+    );
+}
@@ -1,6 +1,8 @@
-#![cfg(test)]
-
+use crate::bigint::{BigInt, Number};
+use crate::checked;
+#[cfg(test)]
 use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Settings, Solution};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum State {
@@ -26,25 +28,30 @@ struct Info {
 }
 
 impl Info {
-    fn parse(line: &str) -> Self {
-        let (states, runs) = line.split_once(|c: char| c.is_whitespace()).unwrap();
+    fn parse(line: &str) -> Res<Self> {
+        let (states, runs) = line
+            .split_once(|c: char| c.is_whitespace())
+            .ok_or_else(|| format!("expected states and runs, got '{line}'"))?;
 
         let states = states
             .chars()
             .map(|c| match c {
-                '#' => State::Operational,
-                '.' => State::Damaged,
-                '?' => State::Unknown,
-                _ => panic!("unexpected state"),
+                '#' => Ok(State::Operational),
+                '.' => Ok(State::Damaged),
+                '?' => Ok(State::Unknown),
+                _ => Err(format!("unexpected state '{c}'")),
             })
-            .collect();
+            .collect::<Res<_>>()?;
 
         let runs = runs
             .split(',')
-            .map(|val| val.parse::<u32>().expect("not a number"))
-            .collect();
+            .map(|val| {
+                val.parse::<u32>()
+                    .map_err(|e| format!("invalid run '{val}': {e}"))
+            })
+            .collect::<Res<_>>()?;
 
-        Self { states, runs }
+        Ok(Self { states, runs })
     }
 
     fn unfold(&self, times: usize) -> Self {
@@ -60,13 +67,13 @@ impl Info {
         Self { states, runs }
     }
 
-    fn possible_arrangments(&self) -> usize {
-        fn recursive(states: &[State], runs: &[u32]) -> usize {
+    fn possible_arrangments(&self) -> Res<usize> {
+        fn recursive(states: &[State], runs: &[u32]) -> Res<usize> {
             let Some((&run, runs)) = runs.split_first() else {
                 if states.contains(&State::Operational) {
-                    return 0;
+                    return Ok(0);
                 } else {
-                    return 1;
+                    return Ok(1);
                 }
             };
             let run = run as usize;
@@ -93,7 +100,7 @@ impl Info {
                     }
 
                     // Count the final run
-                    sum += 1;
+                    sum = checked::add(sum, 1, "arrangements")?;
                     break;
                 }
 
@@ -125,12 +132,20 @@ impl Info {
                             }
                             (false, true) => {
                                 // Must stay
-                                sum += recursive(&states[end + 1..], runs);
+                                sum = checked::add(
+                                    sum,
+                                    recursive(&states[end + 1..], runs)?,
+                                    "arrangements",
+                                )?;
                                 break;
                             }
                             (false, false) => {
                                 // No limitation
-                                sum += recursive(&states[end + 1..], runs);
+                                sum = checked::add(
+                                    sum,
+                                    recursive(&states[end + 1..], runs)?,
+                                    "arrangements",
+                                )?;
                                 pos += 1;
                                 continue;
                             }
@@ -144,7 +159,11 @@ impl Info {
                     State::Unknown => {
                         // Must be a complete run
                         if states[end] != State::Operational {
-                            sum += recursive(&states[end + 1..], runs);
+                            sum = checked::add(
+                                sum,
+                                recursive(&states[end + 1..], runs)?,
+                                "arrangements",
+                            )?;
                         }
 
                         pos += 1;
@@ -152,41 +171,41 @@ impl Info {
                     }
                 }
             }
-            return sum;
+            Ok(sum)
         }
 
         recursive(&self.states, &self.runs)
     }
 
-    fn possible_arrangments_dynamic(&self) -> usize {
+    fn possible_arrangments_dynamic<N: Number>(&self) -> Res<N> {
         // Just inject some dynamic programming to make it faster
-        struct Location {
+        struct Location<N> {
             state: usize,
             run: usize,
-            sum: usize,
+            sum: N,
         }
 
-        type Visited<'a> = &'a mut Vec<Location>;
+        type Visited<'a, N> = &'a mut Vec<Location<N>>;
 
-        fn recursive(states: &[State], runs: &[u32], visited: Visited) -> usize {
+        fn recursive<N: Number>(states: &[State], runs: &[u32], visited: Visited<N>) -> Res<N> {
             if let Some(prev) = visited
                 .iter()
                 .find(|loc| loc.state == states.len() && loc.run == runs.len())
             {
-                return prev.sum;
+                return Ok(prev.sum.clone());
             }
 
             let Some((&run, runs)) = runs.split_first() else {
                 if states.contains(&State::Operational) {
-                    return 0;
+                    return Ok(N::zero());
                 } else {
-                    return 1;
+                    return Ok(N::from(1));
                 }
             };
             let run = run as usize;
 
             let mut pos = 0;
-            let mut sum = 0;
+            let mut sum = N::zero();
             loop {
                 let end = pos + run;
                 if end > states.len() {
@@ -207,7 +226,7 @@ impl Info {
                     }
 
                     // Count the final run
-                    sum += 1;
+                    sum = checked::add(sum, N::from(1), "arrangements")?;
                     break;
                 }
 
@@ -239,12 +258,14 @@ impl Info {
                             }
                             (false, true) => {
                                 // Must stay
-                                sum += recursive(&states[end + 1..], runs, visited);
+                                let rest = recursive(&states[end + 1..], runs, visited)?;
+                                sum = checked::add(sum, rest, "arrangements")?;
                                 break;
                             }
                             (false, false) => {
                                 // No limitation
-                                sum += recursive(&states[end + 1..], runs, visited);
+                                let rest = recursive(&states[end + 1..], runs, visited)?;
+                                sum = checked::add(sum, rest, "arrangements")?;
                                 pos += 1;
                                 continue;
                             }
@@ -258,7 +279,8 @@ impl Info {
                     State::Unknown => {
                         // Must be a complete run
                         if states[end] != State::Operational {
-                            sum += recursive(&states[end + 1..], runs, visited);
+                            let rest = recursive(&states[end + 1..], runs, visited)?;
+                            sum = checked::add(sum, rest, "arrangements")?;
                         }
 
                         pos += 1;
@@ -269,15 +291,75 @@ impl Info {
             visited.push(Location {
                 state: states.len(),
                 run: runs.len() + 1,
-                sum,
+                sum: sum.clone(),
             });
-            return sum;
+            Ok(sum)
         }
 
         recursive(&self.states, &self.runs, &mut Vec::new())
     }
 }
 
+/// `number` counts the arrangements with machine or big integers
+const SETTINGS: &[&str] = &["number"];
+
+pub(crate) struct Day12;
+
+impl Solution for Day12 {
+    fn day(&self) -> u8 {
+        12
+    }
+
+    fn settings(&self) -> &'static [&'static str] {
+        SETTINGS
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        self.solve_with(part, input, &Settings::default())
+    }
+
+    fn solve_with(&self, part: u8, input: &str, settings: &Settings) -> Res<Answer> {
+        settings.check(self.day(), SETTINGS)?;
+        match (part, settings.big()?) {
+            (1, false) => Ok(part1(input)?.into()),
+            // Folded once, the generic count is the part 1 count
+            (1, true) => Ok(unfolded::<BigInt>(input, 1)?.into()),
+            (2, false) => Ok(part2(input)?.into()),
+            (2, true) => Ok(unfolded::<BigInt>(input, 5)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+fn part1(text: &str) -> Res<usize> {
+    let counts = text.lines().map(|line| {
+        let count = Info::parse(line)?.possible_arrangments()?;
+        if count == 0 {
+            return Err(format!("impossible to arrange '{line}'"));
+        }
+        Ok(count)
+    });
+    checked::sum(counts, "sum of arrangements")
+}
+
+/// Arrangements of every line unfolded `times`
+fn unfolded<N: Number>(text: &str, times: usize) -> Res<N> {
+    let counts = text.lines().map(|line| {
+        let count = Info::parse(line)?
+            .unfold(times)
+            .possible_arrangments_dynamic::<N>()?;
+        if count == N::zero() {
+            return Err(format!("impossible to arrange '{line}'"));
+        }
+        Ok(count)
+    });
+    checked::sum(counts, "sum of arrangements")
+}
+
+fn part2(text: &str) -> Res<usize> {
+    unfolded(text, 5)
+}
+
 #[test]
 fn task1() {
     let text = open_first(&[
@@ -286,15 +368,7 @@ fn task1() {
     ])
     .unwrap();
 
-    let result: usize = text
-        .lines()
-        .map(|line| {
-            let count = Info::parse(line).possible_arrangments();
-            assert_ne!(count, 0, "impossible to arrange");
-            return count;
-        })
-        .sum();
-
+    let result = part1(&text).unwrap();
     println!("Result: {}", result);
 }
 
@@ -306,14 +380,35 @@ fn task2() {
     ])
     .unwrap();
 
-    let result: usize = text
-        .lines()
-        .map(|line| {
-            let count = Info::parse(line).unfold(5).possible_arrangments_dynamic();
-            assert_ne!(count, 0, "impossible to arrange");
-            return count;
-        })
-        .sum();
-
+    let result = part2(&text).unwrap();
     println!("Result: {}", result);
 }
+
+#[test]
+fn unfold_further() {
+    let text = open_first(&["src/day12/sample.txt"]).unwrap();
+    assert_eq!(
+        unfolded::<BigInt>(&text, 5).unwrap().to_string(),
+        part2(&text).unwrap().to_string()
+    );
+    // Unfolded 20 times the last line alone has 10 * 15^19 arrangements
+    let big = unfolded::<BigInt>(&text, 20).unwrap();
+    assert!(big > BigInt::from(u64::MAX));
+    assert_eq!(
+        big.to_string(),
+        unfolded::<i128>(&text, 20).unwrap().to_string()
+    );
+    assert!(Info::parse("?#? 1,x").is_err());
+
+    // The `number` setting runs the same counts on BigInt
+    let big = [("number", "big")].into_iter().collect::<Settings>();
+    for part in 1..=2 {
+        let machine = Day12.solve(part, &text).unwrap();
+        let answer = Day12.solve_with(part, &text, &big).unwrap();
+        assert!(matches!(answer, Answer::Big(_)));
+        assert_eq!(answer.to_string(), machine.to_string());
+    }
+    let other = [("number", "float")].into_iter().collect::<Settings>();
+    assert!(Day12.solve_with(1, &text, &other).is_err());
+    assert!(part1("#.# 2").is_err());
+}
@@ -1,143 +1,70 @@
-#![cfg(test)]
-
+use crate::bitgrid::BitGrid;
+use crate::checked;
+#[cfg(test)]
 use crate::open_first;
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Cell {
-    Ash,
-    Rock,
-}
-
-impl Cell {
-    fn symbol(&self) -> char {
-        match self {
-            Cell::Ash => '.',
-            Cell::Rock => '#',
+use crate::solution::{missing_part, Answer, Res, Solution};
+
+fn parse_list(text: &str) -> Res<Vec<BitGrid>> {
+    let mut lines = text.lines();
+    let mut grids = Vec::new();
+    loop {
+        let block = lines.by_ref().take_while(|line| !line.is_empty());
+        let grid = BitGrid::parse(block, '#', '.')
+            .map_err(|e| format!("grid {}: {e}", grids.len() + 1))?;
+        if grid.rows().is_empty() {
+            return Ok(grids);
         }
+        grids.push(grid);
     }
-
-    fn parse(c: char) -> Option<Self> {
-        match c {
-            '.' => Some(Cell::Ash),
-            '#' => Some(Cell::Rock),
-            _ => None,
-        }
-    }
-}
-
-fn has_mirror(line: &[Cell], at: usize) -> bool {
-    line[0..at]
-        .iter()
-        .rev()
-        .zip(&line[at..])
-        .all(|(f, r)| *f == *r)
-}
-
-fn smudge_mirror(line: &[Cell], at: usize) -> usize {
-    line[0..at]
-        .iter()
-        .rev()
-        .zip(&line[at..])
-        .filter(|(f, r)| *f != *r)
-        .count()
 }
 
-struct Grid {
-    rows: usize,
-    cols: usize,
-    cells: Vec<Cell>,
+/// Column left of which the grid mirrors with exactly `smudges` cells off
+fn find_mirror(grid: &BitGrid, smudges: u32) -> Option<usize> {
+    (1..grid.cols()).find(|&at| grid.vertical_mirror_diff(at) == smudges)
 }
 
-impl Grid {
-    fn new_from(cols: usize, cells: Vec<Cell>) -> Self {
-        let rows = cells.len() / cols;
-        assert_eq!(cols * rows, cells.len());
-        Grid { rows, cols, cells }
-    }
-
-    fn parse_list(text: &str) -> Vec<Grid> {
-        let mut lines = text.lines();
-        let mut width = 0;
-        let mut cells = Vec::new();
-        let mut grids = Vec::new();
-
-        while let Some(line) = lines.next() {
-            if !line.is_empty() {
-                if cells.is_empty() {
-                    width = line.len()
-                }
-                cells.extend(line.chars().map(|c| Cell::parse(c).expect("invalid cell")));
-            } else {
-                grids.push(Grid::new_from(width, std::mem::take(&mut cells)));
-            }
+fn summarize(text: &str, smudges: u32) -> Res<usize> {
+    let notes = parse_list(text)?.into_iter().enumerate().map(|(idx, g)| {
+        if let Some(pos) = find_mirror(&g, smudges) {
+            // println!("mirror({}):\n{}", pos, g);
+            return Ok(pos);
         }
-        if !cells.is_empty() {
-            grids.push(Grid::new_from(width, cells));
+
+        let t = g
+            .transpose()
+            .map_err(|e| format!("grid {}: {e}", idx + 1))?;
+        if let Some(pos) = find_mirror(&t, smudges) {
+            // println!("mirror({}):\n{}", pos, g);
+            return checked::mul(pos, 100, "rows above");
         }
-        return grids;
-    }
 
-    fn at(&self, col: usize, row: usize) -> Cell {
-        self.cells[row * self.cols + col]
-    }
+        Err(format!("found no mirror in grid {}", idx + 1))
+    });
+    checked::sum(notes, "summary")
+}
 
-    fn transpose(&self) -> Self {
-        let mut cells = Vec::with_capacity(self.cells.len());
-        for col in 0..self.cols {
-            for row in 0..self.rows {
-                cells.push(self.at(col, row));
-            }
-        }
-        Self {
-            rows: self.cols,
-            cols: self.rows,
-            cells,
-        }
-    }
+pub(crate) struct Day13;
 
-    fn find_row_mirror(&self) -> Option<usize> {
-        for pos in 1..self.cols {
-            if self
-                .cells
-                .chunks_exact(self.cols)
-                .all(|row| has_mirror(row, pos))
-            {
-                return Some(pos);
-            }
-        }
-        return None;
+impl Solution for Day13 {
+    fn day(&self) -> u8 {
+        13
     }
 
-    fn find_row_mirror_smudge(&self) -> Option<usize> {
-        for pos in 1..self.cols {
-            let count = self.cells.chunks_exact(self.cols).fold(0, |count, row| {
-                if count < 2 {
-                    count + smudge_mirror(row, pos)
-                } else {
-                    count
-                }
-            });
-            // println!("count[{}]: {}", pos, count);
-            if count == 1 {
-                return Some(pos);
-            }
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        match part {
+            1 => Ok(part1(input)?.into()),
+            2 => Ok(part2(input)?.into()),
+            _ => missing_part(self.day(), part),
         }
-        return None;
     }
 }
 
-impl std::fmt::Debug for Grid {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut out = String::with_capacity(self.rows * self.cols + self.rows);
-        for row in self.cells.chunks_exact(self.cols) {
-            for cell in row {
-                out.push(cell.symbol());
-                out.push(' ');
-            }
-            out.push('\n');
-        }
-        f.write_str(&out)
-    }
+fn part1(text: &str) -> Res<usize> {
+    summarize(text, 0)
+}
+
+fn part2(text: &str) -> Res<usize> {
+    summarize(text, 1)
 }
 
 #[test]
@@ -148,24 +75,7 @@ fn task1() {
     ])
     .unwrap();
 
-    let result: usize = Grid::parse_list(&text)
-        .iter()
-        .map(|g| {
-            if let Some(pos) = g.find_row_mirror() {
-                println!("mirror({}):\n{:?}", pos, g);
-                return pos;
-            }
-
-            let t = g.transpose();
-            if let Some(pos) = t.find_row_mirror() {
-                println!("mirror({}):\n{:?}", pos, g);
-                return pos * 100;
-            }
-
-            panic!("found no mirror");
-        })
-        .sum();
-
+    let result = part1(&text).unwrap();
     println!("Result: {}", result);
 }
 
@@ -177,23 +87,6 @@ fn task2() {
     ])
     .unwrap();
 
-    let result: usize = Grid::parse_list(&text)
-        .iter()
-        .map(|g| {
-            if let Some(pos) = g.find_row_mirror_smudge() {
-                println!("mirror({}):\n{:?}", pos, g);
-                return pos;
-            }
-
-            let t = g.transpose();
-            if let Some(pos) = t.find_row_mirror_smudge() {
-                println!("mirror({}):\n{:?}", pos, g);
-                return pos * 100;
-            }
-
-            panic!("found no mirror");
-        })
-        .sum();
-
+    let result = part2(&text).unwrap();
     println!("Result: {}", result);
 }
@@ -1,8 +1,12 @@
-#![cfg(test)]
-
+use crate::checked;
+use crate::coord::{Bounds, Dir4, Pos};
+use crate::cycle;
+use crate::explore::Simulation;
+#[cfg(test)]
 use crate::open_first;
+use crate::solution::{missing_part, Answer, Res, Solution};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 enum Rock {
     Ball,
     Square,
@@ -35,55 +39,225 @@ fn transpose(grid: &[Rock], cols: usize) -> Vec<Rock> {
             out.push(row[col])
         }
     }
-    return out;
+    out
 }
 
-#[test]
-fn task1() {
-    let text = open_first(&[
-        "src/day14/input.txt",  //
-        "src/day14/sample.txt", //
-    ])
-    .unwrap();
+/// Rocks rolling north, every ball moves at most one cell per step
+#[derive(Clone)]
+pub(crate) struct Rolling {
+    rocks: Vec<Rock>,
+    cols: usize,
+    steps: usize,
+}
 
-    // println!("{}", text);
+impl Rolling {
+    pub(crate) fn parse(text: &str) -> Res<Self> {
+        let cols = text.lines().next().map_or(0, |line| line.chars().count());
+        if cols == 0 {
+            return Err("empty platform".to_string());
+        }
+        let mut rocks = Vec::with_capacity(text.len());
+        for (idx, line) in text.lines().enumerate() {
+            if line.chars().count() != cols {
+                return Err(format!("line {}: expected {cols} rocks", idx + 1));
+            }
+            for c in line.chars() {
+                let rock =
+                    Rock::parse(c).ok_or_else(|| format!("line {}: not a rock '{c}'", idx + 1))?;
+                rocks.push(rock);
+            }
+        }
+        Ok(Self {
+            rocks,
+            cols,
+            steps: 0,
+        })
+    }
+
+    fn load(&self) -> Res<usize> {
+        let rows = self.rocks.len() / self.cols;
+        let loads = self
+            .rocks
+            .chunks_exact(self.cols)
+            .enumerate()
+            .map(|(row, line)| {
+                let balls = line.iter().filter(|r| matches!(r, Rock::Ball)).count();
+                checked::mul(balls, rows - row, "row load")
+            });
+        checked::sum(loads, "load")
+    }
+}
 
-    let rocks = text
-        .lines()
-        .flat_map(|line| line.chars().map(|c| Rock::parse(c).expect("not a rock")))
-        .collect::<Vec<_>>();
+impl Simulation for Rolling {
+    fn step(&mut self) -> bool {
+        let mut moved = false;
+        let bounds = Bounds {
+            cols: self.cols,
+            rows: self.rocks.len() / self.cols,
+        };
+        for index in 0..self.rocks.len() {
+            let pos = Pos::from_index(index, self.cols);
+            let Some(above) = pos.checked_step(Dir4::N, bounds) else {
+                continue;
+            };
+            let above = above.index(self.cols);
+            if let (Rock::Ball, Rock::Empty) = (self.rocks[index], self.rocks[above]) {
+                self.rocks.swap(index, above);
+                moved = true;
+            }
+        }
+        self.steps += moved as usize;
+        moved
+    }
+}
+
+impl std::fmt::Display for Rolling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rocks.chunks_exact(self.cols) {
+            let line = row.iter().map(|r| r.symbol()).collect::<String>();
+            writeln!(f, "{}", line)?;
+        }
+        match self.load() {
+            Ok(load) => write!(f, "load: {load}"),
+            Err(e) => write!(f, "load: {e}"),
+        }
+    }
+}
 
-    let rows = text.lines().count();
-    let cols = rocks.len() / rows;
-    assert_eq!(rows * cols, rocks.len());
+pub(crate) struct Day14;
 
-    println!("rocks:");
-    for row in rocks.chunks_exact(cols) {
-        let line = row.iter().map(|r| r.symbol()).collect::<String>();
-        println!("{}", line);
+impl Solution for Day14 {
+    fn day(&self) -> u8 {
+        14
     }
 
+    fn solve(&self, part: u8, input: &str) -> Res<Answer> {
+        match part {
+            1 => Ok(part1(input)?.into()),
+            2 => Ok(part2(input)?.into()),
+            _ => missing_part(self.day(), part),
+        }
+    }
+}
+
+fn part1(text: &str) -> Res<usize> {
+    // println!("{}", text);
+
+    let Rolling { rocks, cols, .. } = Rolling::parse(text)?;
+    let rows = rocks.len() / cols;
+
+    // println!("rocks:");
+    // for row in rocks.chunks_exact(cols) {
+    //     let line = row.iter().map(|r| r.symbol()).collect::<String>();
+    //     println!("{}", line);
+    // }
+
     let rocks = transpose(&rocks, cols);
     let cols = rows;
 
-    let result: usize = rocks
-        .chunks_exact(cols)
-        .map(|row| {
-            let mut next = row.len();
-            let mut sum = 0;
-            for (index, rock) in row.iter().enumerate() {
-                match rock {
-                    Rock::Ball => {
-                        sum += next;
-                        next -= 1;
-                    }
-                    Rock::Square => next = row.len() - index - 1,
-                    Rock::Empty => (),
+    let loads = rocks.chunks_exact(cols).map(|row| {
+        let mut next = row.len();
+        let mut sum = 0;
+        for (index, rock) in row.iter().enumerate() {
+            match rock {
+                Rock::Ball => {
+                    sum = checked::add(sum, next, "column load")?;
+                    next -= 1;
                 }
+                Rock::Square => next = row.len() - index - 1,
+                Rock::Empty => (),
             }
-            return sum;
-        })
-        .sum();
+        }
+        Ok(sum)
+    });
+    checked::sum(loads, "load")
+}
+
+/// Roll every ball north as far as it goes
+fn tilt_north(rocks: &mut [Rock], cols: usize) {
+    let mut free = vec![0; cols];
+    for index in 0..rocks.len() {
+        let pos = Pos::from_index(index, cols);
+        match rocks[index] {
+            Rock::Square => free[pos.x] = pos.y + 1,
+            Rock::Ball => {
+                rocks.swap(index, Pos::new(pos.x, free[pos.x]).index(cols));
+                free[pos.x] += 1;
+            }
+            Rock::Empty => (),
+        }
+    }
+}
+
+/// Turn the platform clockwise, returns the new column count
+fn rotate(rocks: &[Rock], cols: usize) -> (Vec<Rock>, usize) {
+    let rows = rocks.len() / cols;
+    let mut out = Vec::with_capacity(rocks.len());
+    for x in 0..cols {
+        for y in (0..rows).rev() {
+            out.push(rocks[Pos::new(x, y).index(cols)]);
+        }
+    }
+    (out, rows)
+}
 
+/// Tilt north, west, south and east, turning the platform after each tilt
+fn spin(rocks: &[Rock], cols: usize) -> Vec<Rock> {
+    let (mut rocks, mut cols) = (rocks.to_vec(), cols);
+    for _ in 0..4 {
+        tilt_north(&mut rocks, cols);
+        (rocks, cols) = rotate(&rocks, cols);
+    }
+    rocks
+}
+
+fn part2(text: &str) -> Res<usize> {
+    let platform = Rolling::parse(text)?;
+    let cols = platform.cols;
+    let step = |rocks: &Vec<Rock>| spin(rocks, cols);
+    let cycle = cycle::brent(platform.rocks.clone(), step);
+    let rocks = cycle.fast_forward(platform.rocks, step, 1_000_000_000);
+    Rolling {
+        rocks,
+        cols,
+        steps: 0,
+    }
+    .load()
+}
+
+#[test]
+fn task1() {
+    let text = open_first(&[
+        "src/day14/input.txt",  //
+        "src/day14/sample.txt", //
+    ])
+    .unwrap();
+
+    let result = part1(&text).unwrap();
+    println!("Result: {}", result);
+}
+
+#[test]
+fn task2() {
+    let text = open_first(&[
+        "src/day14/input.txt",  //
+        "src/day14/sample.txt", //
+    ])
+    .unwrap();
+
+    let result = part2(&text).unwrap();
     println!("Result: {}", result);
 }
+
+#[test]
+fn rolling() {
+    // Rolling one cell per step must settle where the tilt puts every ball
+    let text = open_first(&["src/day14/sample.txt"]).unwrap();
+    let mut rolling = Rolling::parse(&text).unwrap();
+    while rolling.step() {}
+    assert_eq!(rolling.load(), part1(&text));
+    assert!(part1("O.x\n").is_err());
+    assert!(Rolling::parse("").is_err());
+    assert!(Rolling::parse("O.#\nO.\n").is_err());
+    assert!(Rolling::parse("O.x\n").is_err());
+}
@@ -0,0 +1,251 @@
+//! Interactive step-through explorer for puzzle simulations.
+//!
+//! A day plugs in by implementing [`Simulation`] for a cheaply clonable state,
+//! the explorer keeps every visited state around so stepping back is free.
+
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+
+/// Upper bound on steps taken by a single `until` command
+const UNTIL_LIMIT: usize = 10_000_000;
+
+pub(crate) trait Simulation: Clone + Display {
+    /// Advance a single step, returns `false` if the simulation has already finished
+    fn step(&mut self) -> bool;
+
+    /// Names of the conditions understood by [`Simulation::check`]
+    fn conditions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Evaluate a named condition, `None` if the name is unknown
+    fn check(&self, _name: &str) -> Option<bool> {
+        None
+    }
+}
+
+enum Command {
+    Step,
+    Back,
+    Jump(isize),
+    Until(String),
+    Help,
+    Quit,
+}
+
+impl Command {
+    fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let Some(word) = words.next() else {
+            return Some(Self::Step);
+        };
+        let arg = words.next();
+        Some(match word {
+            "n" | "next" => Self::Step,
+            "b" | "back" => Self::Back,
+            "j" | "jump" => Self::Jump(arg?.parse().ok()?),
+            "u" | "until" => Self::Until(arg.unwrap_or("done").to_string()),
+            "h" | "help" | "?" => Self::Help,
+            "q" | "quit" => Self::Quit,
+            _ => return None,
+        })
+    }
+}
+
+struct Explorer<S> {
+    history: Vec<S>,
+    current: usize,
+    finished: bool,
+}
+
+impl<S: Simulation> Explorer<S> {
+    fn new(init: S) -> Self {
+        Self {
+            history: vec![init],
+            current: 0,
+            finished: false,
+        }
+    }
+
+    fn state(&self) -> &S {
+        &self.history[self.current]
+    }
+
+    fn forward(&mut self) -> bool {
+        if self.current + 1 < self.history.len() {
+            self.current += 1;
+            return true;
+        }
+        if self.finished {
+            return false;
+        }
+        let mut next = self.state().clone();
+        if !next.step() {
+            self.finished = true;
+            return false;
+        }
+        self.history.push(next);
+        self.current += 1;
+        true
+    }
+
+    fn back(&mut self) -> bool {
+        let moved = self.current > 0;
+        self.current = self.current.saturating_sub(1);
+        moved
+    }
+
+    fn jump(&mut self, count: isize) {
+        if count < 0 {
+            self.current = self.current.saturating_sub(count.unsigned_abs());
+        } else {
+            for _ in 0..count {
+                if !self.forward() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn until(&mut self, name: &str) -> Result<(), String> {
+        if name == "done" {
+            for _ in 0..UNTIL_LIMIT {
+                if !self.forward() {
+                    return Ok(());
+                }
+            }
+            return Err(format!("not done after {UNTIL_LIMIT} steps"));
+        }
+        let Some(mut hit) = self.state().check(name) else {
+            return Err(format!(
+                "unknown condition '{name}', expected one of: done {}",
+                self.state().conditions().join(" ")
+            ));
+        };
+        // Always take a step, so repeating the command finds the next hit
+        let mut steps = 0;
+        while steps == 0 || !hit {
+            if steps == UNTIL_LIMIT || !self.forward() {
+                return Err(format!("condition '{name}' not reached"));
+            }
+            hit = self.state().check(name).unwrap_or(false);
+            steps += 1;
+        }
+        Ok(())
+    }
+}
+
+const HELP: &str = "\
+commands:
+  <enter> | n       step forward
+  b                 step back
+  j N               jump N steps (negative to go back)
+  u [COND]          run until condition holds (default: done)
+  h                 this help
+  q                 quit";
+
+/// Run the explorer on `init`, reading commands from `input` until it is exhausted or `q`
+pub(crate) fn explore<S: Simulation>(
+    init: S,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> std::io::Result<()> {
+    let mut explorer = Explorer::new(init);
+    let mut message = String::from("h for help");
+    let mut line = String::new();
+    loop {
+        write!(output, "\x1b[2J\x1b[H")?;
+        writeln!(output, "{}", explorer.state())?;
+        let done = if explorer.finished && explorer.current + 1 == explorer.history.len() {
+            " (done)"
+        } else {
+            ""
+        };
+        writeln!(output, "step {}{} | {}", explorer.current, done, message)?;
+        write!(output, "> ")?;
+        output.flush()?;
+
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        message.clear();
+        match Command::parse(&line) {
+            Some(Command::Step) => {
+                if !explorer.forward() {
+                    message += "simulation is done";
+                }
+            }
+            Some(Command::Back) => {
+                if !explorer.back() {
+                    message += "at first step";
+                }
+            }
+            Some(Command::Jump(count)) => explorer.jump(count),
+            Some(Command::Until(name)) => {
+                if let Err(err) = explorer.until(&name) {
+                    message += &err;
+                }
+            }
+            Some(Command::Help) => message += HELP,
+            Some(Command::Quit) => return Ok(()),
+            None => message += "unknown command, h for help",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Counter(u32);
+
+    impl Display for Counter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "[{}]", self.0)
+        }
+    }
+
+    impl Simulation for Counter {
+        fn step(&mut self) -> bool {
+            self.0 += 1;
+            self.0 <= 10
+        }
+        fn conditions(&self) -> &'static [&'static str] {
+            &["even"]
+        }
+        fn check(&self, name: &str) -> Option<bool> {
+            (name == "even").then_some(self.0.is_multiple_of(2))
+        }
+    }
+
+    #[test]
+    fn history() {
+        let mut explorer = Explorer::new(Counter(0));
+        explorer.jump(3);
+        assert_eq!(explorer.state().0, 3);
+        assert!(explorer.back());
+        assert_eq!(explorer.state().0, 2);
+        explorer.until("even").unwrap();
+        assert_eq!(explorer.state().0, 4);
+        explorer.until("done").unwrap();
+        assert_eq!(explorer.state().0, 10);
+        assert!(explorer.until("odd").is_err());
+        explorer.jump(-20);
+        assert_eq!(explorer.current, 0);
+    }
+
+    #[test]
+    fn scripted() {
+        let text = crate::open_first(&["src/day14/sample.txt"]).unwrap();
+        let rolling = crate::day14::Rolling::parse(&text).unwrap();
+        let mut output = Vec::new();
+        explore(rolling, "n\nb\nu done\nq\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let screens = output.split("\x1b[2J\x1b[H").collect::<Vec<_>>();
+        assert!(screens[2].contains("step 1 |"));
+        assert!(screens[3].contains("step 0 |"));
+        assert!(screens[4].contains("load: 136\nstep 7 (done) |"));
+    }
+}
@@ -0,0 +1,120 @@
+//! Lattice polygons: shoelace area, boundary points, Pick's theorem and point location.
+//!
+//! A polygon is its corners in order around it, the last joined back to the first.
+
+use crate::checked;
+use crate::coord::Point;
+use crate::math;
+use crate::solution::Res;
+
+fn edges(poly: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    poly.iter()
+        .copied()
+        .zip(poly.iter().copied().cycle().skip(1))
+}
+
+/// Twice the area by the shoelace formula, always a whole number for lattice corners
+pub(crate) fn double_area(poly: &[Point]) -> Res<i64> {
+    let terms = edges(poly).map(|(a, b)| {
+        let (ab, ba) = (
+            checked::mul(a.x, b.y, "shoelace term")?,
+            checked::mul(b.x, a.y, "shoelace term")?,
+        );
+        checked::sub(ab, ba, "shoelace term")
+    });
+    let area = checked::sum(terms, "double area")?;
+    area.checked_abs()
+        .ok_or_else(|| format!("double area overflows i64: |{area}|"))
+}
+
+/// Lattice points on the edges
+pub(crate) fn boundary_points(poly: &[Point]) -> Res<i64> {
+    let counts = edges(poly).map(|(a, b)| {
+        let d = b - a;
+        let count = math::gcd(d.x.into(), d.y.into());
+        i64::try_from(count).map_err(|_| format!("{count} boundary points do not fit i64"))
+    });
+    checked::sum(counts, "boundary points")
+}
+
+/// Lattice points strictly inside, by Pick's theorem `A = I + B / 2 - 1`
+pub(crate) fn interior_points(poly: &[Point]) -> Res<i64> {
+    let twice = checked::sub(
+        double_area(poly)?,
+        boundary_points(poly)?,
+        "interior points",
+    )?;
+    checked::add(twice / 2, 1, "interior points")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+/// Where `point` lies, by casting a ray towards positive x
+// Not used by day10 since Pick's theorem counts directly
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn locate(poly: &[Point], p: Point) -> Location {
+    let mut inside = false;
+    for (a, b) in edges(poly) {
+        let (edge, to) = (b - a, p - a);
+        let cross = edge.x * to.y - edge.y * to.x;
+        let within = a.x.min(b.x) <= p.x
+            && p.x <= a.x.max(b.x)
+            && a.y.min(b.y) <= p.y
+            && p.y <= a.y.max(b.y);
+        if cross == 0 && within {
+            return Location::Boundary;
+        }
+        // Half open in y, so a ray through a corner counts once
+        if (a.y > p.y) != (b.y > p.y) {
+            // Crossing right of the point, the sign flips with the edge direction
+            let right = if b.y > a.y { cross > 0 } else { cross < 0 };
+            inside ^= right;
+        }
+    }
+    if inside {
+        Location::Inside
+    } else {
+        Location::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(corners: &[(i64, i64)]) -> Vec<Point> {
+        corners.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    #[test]
+    fn lattice() {
+        // An L of area 12, 6 by 2 plus 2 by 4 minus the overlap
+        let poly = points(&[(0, 0), (6, 0), (6, 2), (2, 2), (2, 4), (0, 4)]);
+        assert_eq!(double_area(&poly), Ok(32));
+        assert_eq!(boundary_points(&poly), Ok(20));
+        assert_eq!(interior_points(&poly), Ok(7));
+
+        let counted = (0..=6)
+            .flat_map(|x| (0..=4).map(move |y| Point::new(x, y)))
+            .filter(|&p| locate(&poly, p) == Location::Inside)
+            .count();
+        assert_eq!(counted, 7);
+        assert_eq!(locate(&poly, Point::new(1, 1)), Location::Inside);
+        assert_eq!(locate(&poly, Point::new(4, 2)), Location::Boundary);
+        assert_eq!(locate(&poly, Point::new(0, 3)), Location::Boundary);
+        assert_eq!(locate(&poly, Point::new(4, 3)), Location::Outside);
+        assert_eq!(locate(&poly, Point::new(-1, 2)), Location::Outside);
+
+        // Diagonal edges
+        let triangle = points(&[(0, 0), (4, 0), (0, 4)]);
+        assert_eq!(boundary_points(&triangle), Ok(12));
+        assert_eq!(interior_points(&triangle), Ok(3));
+        assert_eq!(locate(&triangle, Point::new(2, 2)), Location::Boundary);
+        assert_eq!(locate(&triangle, Point::new(1, 1)), Location::Inside);
+    }
+}
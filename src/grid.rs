@@ -0,0 +1,91 @@
+//! Rectangular grid of cells parsed from the lines of a puzzle input.
+
+use std::ops::Index;
+
+use crate::coord::{Bounds, Dir4, Pos};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Grid<T> {
+    width: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// One cell per char, `None` on an unknown char, an empty first line or lines of different lengths
+    pub(crate) fn parse(text: &str, mut cell: impl FnMut(char) -> Option<T>) -> Option<Self> {
+        let width = text.lines().next()?.chars().count();
+        if width == 0 {
+            return None;
+        }
+        let mut cells = Vec::with_capacity(text.len());
+        for line in text.lines() {
+            if line.chars().count() != width {
+                return None;
+            }
+            for c in line.chars() {
+                cells.push(cell(c)?);
+            }
+        }
+        Some(Self { width, cells })
+    }
+
+    pub(crate) fn cols(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn rows(&self) -> usize {
+        self.cells.len() / self.width
+    }
+
+    pub(crate) fn bounds(&self) -> Bounds {
+        Bounds {
+            cols: self.cols(),
+            rows: self.rows(),
+        }
+    }
+
+    pub(crate) fn get(&self, pos: Pos) -> Option<&T> {
+        (pos.x < self.width).then(|| self.cells.get(pos.index(self.width)))?
+    }
+
+    /// Position of the first cell matching `pred`, row by row
+    pub(crate) fn position(&self, pred: impl FnMut(&T) -> bool) -> Option<Pos> {
+        let idx = self.cells.iter().position(pred)?;
+        Some(Pos::from_index(idx, self.width))
+    }
+
+    pub(crate) fn lines(&self) -> std::slice::ChunksExact<'_, T> {
+        self.cells.chunks_exact(self.width)
+    }
+
+    /// Positions of the up to four cells sharing an edge with `pos`
+    // Only used by grid_bfs
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn neighbours(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        pos.neighbours(Dir4::ALL, self.bounds())
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos).expect("position outside the grid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let grid = Grid::parse("ab\ncd\n", Some).unwrap();
+        assert_eq!((grid.cols(), grid.rows()), (2, 2));
+        assert_eq!(grid[Pos::new(1, 1)], 'd');
+        assert_eq!(grid.get(Pos::new(2, 0)), None);
+        assert!(Grid::parse("", Some).is_none());
+        assert!(Grid::parse("\nab\n", Some).is_none());
+        assert!(Grid::parse("ab\nc\n", Some).is_none());
+    }
+}
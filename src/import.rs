@@ -0,0 +1,283 @@
+//! Imports samples and answers from a locally saved puzzle page.
+//!
+//! Every `<pre><code>` block becomes a `sampleN.txt`, unless the day already has
+//! a sample with the same text. An example answer is the last highlighted
+//! `<code><em>` after a block and before the next one, and belongs to that block.
+//! An article without blocks answers for the last block before it.
+//! Answers already given, "Your puzzle answer was", are recorded for the input.
+
+use std::path::Path;
+
+use crate::manifest::Manifest;
+use crate::solution::Res;
+
+#[derive(Debug, Default, PartialEq)]
+struct PagePart {
+    /// Example answers by sample
+    examples: Vec<(usize, String)>,
+    answer: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Page {
+    samples: Vec<String>,
+    parts: Vec<PagePart>,
+}
+
+/// Text of an html fragment, without tags and with entities decoded
+fn text_of(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(pos) = rest.find(['<', '&']) {
+        out += &rest[..pos];
+        rest = &rest[pos..];
+        if rest.starts_with('<') {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+        let Some(end) = rest.find(';').filter(|&end| end < 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let decoded = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            num => num
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| num.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out + rest
+}
+
+/// Every `open .. close` span of `html`, as (start, inner)
+fn spans<'a>(html: &'a str, open: &'a str, close: &'a str) -> Vec<(usize, &'a str)> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = html[pos..].find(open).map(|i| pos + i + open.len()) {
+        let Some(end) = html[start..].find(close).map(|i| start + i) else {
+            break;
+        };
+        out.push((start, &html[start..end]));
+        pos = end + close.len();
+    }
+    out
+}
+
+/// The last highlighted answer of `html`
+fn highlighted(html: &str) -> Option<String> {
+    [
+        ("<code><em>", "</em></code>"),
+        ("<em><code>", "</code></em>"),
+    ]
+    .into_iter()
+    .flat_map(|(open, close)| spans(html, open, close))
+    .max_by_key(|(pos, _)| *pos)
+    .map(|(_, text)| text_of(text).trim().to_string())
+}
+
+fn parse(html: &str) -> Page {
+    let mut page = Page::default();
+    // Sample of the last block seen, in any article
+    let mut last = None;
+    let articles = spans(html, "<article", "</article>");
+    for (idx, &(start, article)) in articles.iter().enumerate() {
+        let mut part = PagePart::default();
+
+        let blocks = spans(article, "<pre><code>", "</code></pre>");
+        if blocks.is_empty() {
+            if let (Some(sample), Some(example)) = (last, highlighted(article)) {
+                part.examples.push((sample, example));
+            }
+        }
+        for (nth, &(at, block)) in blocks.iter().enumerate() {
+            let text = text_of(block);
+            let sample = match page.samples.iter().position(|sample| *sample == text) {
+                Some(pos) => pos,
+                None => {
+                    page.samples.push(text);
+                    page.samples.len() - 1
+                }
+            };
+            last = Some(sample);
+
+            // Up to the next block, or the end of the article
+            let end = blocks.get(nth + 1).map_or(article.len(), |next| next.0);
+            if let Some(example) = highlighted(&article[at + block.len()..end]) {
+                part.examples.push((sample, example));
+            }
+        }
+
+        // The given answer follows the article, before the next one
+        let end = articles.get(idx + 1).map_or(html.len(), |next| next.0);
+        let after = &html[start + article.len()..end];
+        part.answer = spans(after, "Your puzzle answer was <code>", "</code>")
+            .first()
+            .map(|(_, text)| text_of(text).trim().to_string());
+
+        page.parts.push(part);
+    }
+    page
+}
+
+/// Stem of an existing sample of `dir` with the text `sample`
+fn existing(dir: &Path, sample: &str) -> Option<String> {
+    let mut stems = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let stem = path.file_stem()?.to_str()?;
+            let found = path.extension()? == "txt" && stem.starts_with("sample");
+            found.then(|| stem.to_string())
+        })
+        .collect::<Vec<_>>();
+    stems.sort();
+    stems.into_iter().find(|stem| {
+        std::fs::read_to_string(dir.join(format!("{stem}.txt"))).is_ok_and(|old| old == sample)
+    })
+}
+
+/// Write the samples of a saved page into `dir` and its answers into the manifest
+pub(crate) fn import(
+    day: u8,
+    html: &Path,
+    dir: &Path,
+    manifest: &Path,
+    force: bool,
+) -> Res<Vec<String>> {
+    let text = std::fs::read_to_string(html).map_err(|e| format!("{}: {e}", html.display()))?;
+    let page = parse(&text);
+    if page.parts.is_empty() {
+        return Err(format!("{}: no puzzle article found", html.display()));
+    }
+
+    let mut log = Vec::new();
+    let mut stems = Vec::with_capacity(page.samples.len());
+    for (n, sample) in (1..).zip(&page.samples) {
+        if let Some(stem) = existing(dir, sample) {
+            stems.push(stem);
+            continue;
+        }
+        let stem = format!("sample{n}");
+        let path = dir.join(format!("{stem}.txt"));
+        stems.push(stem);
+        match std::fs::read_to_string(&path) {
+            Ok(_) if !force => {
+                return Err(format!(
+                    "{} differs from the page, use --force to overwrite",
+                    path.display()
+                ))
+            }
+            _ => (),
+        }
+        std::fs::write(&path, sample).map_err(|e| format!("{}: {e}", path.display()))?;
+        log.push(format!("wrote {}", path.display()));
+    }
+
+    let mut answers = Manifest::load(manifest)?;
+    for (part, found) in (1..).zip(&page.parts) {
+        for (sample, example) in &found.examples {
+            let stem = &stems[*sample];
+            answers.insert(day, part, stem, example);
+            log.push(format!("part{part}.{stem} = {example}"));
+        }
+        if let Some(answer) = &found.answer {
+            answers.insert(day, part, "input", answer);
+            log.push(format!("part{part}.input = {answer}"));
+        }
+    }
+    answers.save(manifest)?;
+    Ok(log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page() {
+        let html = r#"<main>
+<article class="day-desc"><h2>--- Day 1 ---</h2><p>For example:</p>
+<pre><code>1abc2
+a&lt;b&gt;1
+</code></pre>
+<p>Adding these together produces <code><em>142</em></code>.</p>
+</article>
+<p>Your puzzle answer was <code>55029</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<p>In this example, the values are <code>29</code>, and <em>adding</em> gives <code><em>281</em></code>.</p>
+</article>
+</main>"#;
+        let page = parse(html);
+        assert_eq!(page.samples, ["1abc2\na<b>1\n"]);
+        assert_eq!(
+            page.parts,
+            [
+                PagePart {
+                    examples: vec![(0, "142".into())],
+                    answer: Some("55029".into()),
+                },
+                PagePart {
+                    examples: vec![(0, "281".into())],
+                    answer: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn examples() {
+        // Day 8: each example has its own answer in the same article
+        let html = r#"<article class="day-desc"><h2>--- Day 8 ---</h2>
+<pre><code>RL
+
+AAA = (BBB, CCC)
+</code></pre>
+<p>Starting with <code>AAA</code>, you reach <code>ZZZ</code> in <code><em>2</em></code> steps.</p>
+<pre><code>LLR
+
+AAA = (BBB, BBB)
+</code></pre>
+<p>This takes <code><em>6</em></code> steps.</p>
+</article>"#;
+        let page = parse(html);
+        assert_eq!(page.samples.len(), 2);
+        assert_eq!(
+            page.parts,
+            [PagePart {
+                examples: vec![(0, "2".into()), (1, "6".into())],
+                answer: None,
+            }]
+        );
+
+        // An unchanged sample is reused, a new one gets the next number
+        let dir = std::env::temp_dir().join(format!("aoc-import-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("sample.txt"), &page.samples[0]).unwrap();
+        let (page_path, manifest) = (dir.join("page.html"), dir.join("answers.toml"));
+        std::fs::write(&page_path, html).unwrap();
+        let log = import(8, &page_path, &dir, &manifest, false);
+        let written = std::fs::read_to_string(dir.join("sample2.txt")).ok();
+        let first = dir.join("sample1.txt").exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(log.unwrap()[1..], ["part1.sample = 2", "part1.sample2 = 6"]);
+        assert_eq!(written.as_ref(), Some(&page.samples[1]));
+        assert!(!first);
+    }
+}
@@ -0,0 +1,258 @@
+//! Sets of integer intervals and piecewise translations of the integers.
+//!
+//! An [`IntervalMap`] moves each of its intervals by an offset and leaves
+//! every other integer in place, like a day05 conversion. Maps compose into
+//! one map, so a chain of conversions can be applied in a single step.
+
+use std::ops::Range;
+
+use crate::solution::Res;
+
+/// Sorted, disjoint intervals, touching ones merged
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct IntervalSet {
+    ranges: Vec<Range<i64>>,
+}
+
+impl IntervalSet {
+    pub(crate) fn ranges(&self) -> &[Range<i64>] {
+        &self.ranges
+    }
+
+    pub(crate) fn min(&self) -> Option<i64> {
+        self.ranges.first().map(|range| range.start)
+    }
+}
+
+impl FromIterator<Range<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Range<i64>>>(iter: I) -> Self {
+        let mut sorted = iter
+            .into_iter()
+            .filter(|range| !range.is_empty())
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|range| range.start);
+        let mut ranges: Vec<Range<i64>> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        Self { ranges }
+    }
+}
+
+/// Adds `offset` to the integers of `range`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Piece {
+    pub(crate) range: Range<i64>,
+    pub(crate) offset: i64,
+}
+
+/// Piecewise translation, the identity outside its pieces
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct IntervalMap {
+    /// Sorted and disjoint, without zero offsets or touching equal offsets
+    pieces: Vec<Piece>,
+}
+
+impl IntervalMap {
+    /// Map of `pieces`, which must not overlap
+    pub(crate) fn new(pieces: impl IntoIterator<Item = Piece>) -> Res<Self> {
+        let mut pieces = pieces
+            .into_iter()
+            .filter(|piece| !piece.range.is_empty())
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|piece| piece.range.start);
+        if let Some(pair) = pieces
+            .windows(2)
+            .find(|pair| pair[1].range.start < pair[0].range.end)
+        {
+            return Err(format!(
+                "pieces {:?} and {:?} overlap",
+                pair[0].range, pair[1].range
+            ));
+        }
+        Ok(Self::normalised(pieces))
+    }
+
+    /// Drop identity pieces and join touching pieces of equal offset, `pieces` sorted and disjoint
+    fn normalised(pieces: Vec<Piece>) -> Self {
+        let mut out: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces.into_iter().filter(|piece| piece.offset != 0) {
+            match out.last_mut() {
+                Some(last)
+                    if last.range.end == piece.range.start && last.offset == piece.offset =>
+                {
+                    last.range.end = piece.range.end
+                }
+                _ => out.push(piece),
+            }
+        }
+        Self { pieces: out }
+    }
+
+    pub(crate) fn get(&self, x: i64) -> i64 {
+        let idx = self.pieces.partition_point(|piece| piece.range.end <= x);
+        match self.pieces.get(idx) {
+            Some(piece) if piece.range.contains(&x) => x + piece.offset,
+            _ => x,
+        }
+    }
+
+    /// Every integer as a piece, the identity gaps included
+    fn segments(&self) -> Vec<Piece> {
+        let mut out = Vec::with_capacity(self.pieces.len() * 2 + 1);
+        let mut pos = i64::MIN;
+        for piece in &self.pieces {
+            if pos < piece.range.start {
+                out.push(Piece {
+                    range: pos..piece.range.start,
+                    offset: 0,
+                });
+            }
+            out.push(piece.clone());
+            pos = piece.range.end;
+        }
+        if pos < i64::MAX {
+            out.push(Piece {
+                range: pos..i64::MAX,
+                offset: 0,
+            });
+        }
+        out
+    }
+
+    /// Pieces of `self` cut to `range`, which the identity gaps fill
+    pub(crate) fn cut(&self, range: Range<i64>) -> impl Iterator<Item = Piece> + '_ {
+        self.segments().into_iter().filter_map(move |piece| {
+            let start = piece.range.start.max(range.start);
+            let end = piece.range.end.min(range.end);
+            (start < end).then_some(Piece {
+                range: start..end,
+                offset: piece.offset,
+            })
+        })
+    }
+
+    /// The map taking `x` to `next.get(self.get(x))`
+    pub(crate) fn then(&self, next: &Self) -> Self {
+        let mut pieces = Vec::new();
+        for piece in self.segments() {
+            let image = piece.range.start + piece.offset..piece.range.end + piece.offset;
+            for part in next.cut(image) {
+                pieces.push(Piece {
+                    range: part.range.start - piece.offset..part.range.end - piece.offset,
+                    offset: piece.offset + part.offset,
+                });
+            }
+        }
+        pieces.sort_by_key(|piece| piece.range.start);
+        Self::normalised(pieces)
+    }
+
+    /// The map undoing `self`, if it is one to one and onto
+    // Not needed by day05, where the chain only runs forwards
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn invert(&self) -> Res<Self> {
+        let mut pieces = self
+            .segments()
+            .into_iter()
+            .map(|piece| Piece {
+                range: piece.range.start + piece.offset..piece.range.end + piece.offset,
+                offset: -piece.offset,
+            })
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|piece| piece.range.start);
+        let mut pos = i64::MIN;
+        for piece in &pieces {
+            if piece.range.start != pos {
+                let what = if piece.range.start < pos {
+                    "overlap"
+                } else {
+                    "leave a gap"
+                };
+                return Err(format!(
+                    "images {what} at {}, not invertible",
+                    piece.range.start
+                ));
+            }
+            pos = piece.range.end;
+        }
+        Ok(Self::normalised(pieces))
+    }
+
+    /// Image of every integer of `set`
+    pub(crate) fn apply(&self, set: &IntervalSet) -> IntervalSet {
+        set.ranges()
+            .iter()
+            .flat_map(|range| self.cut(range.clone()))
+            .map(|piece| piece.range.start + piece.offset..piece.range.end + piece.offset)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pieces: &[(Range<i64>, i64)]) -> IntervalMap {
+        IntervalMap::new(pieces.iter().map(|(range, offset)| Piece {
+            range: range.clone(),
+            offset: *offset,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn sets() {
+        let set = IntervalSet::from_iter([5..8, 0..2, 2..3, 7..10, 4..4]);
+        assert_eq!(set.ranges(), [0..3, 5..10]);
+        assert_eq!(set.min(), Some(0));
+    }
+
+    #[test]
+    fn maps() {
+        // Swap 0..5 with 10..15
+        let swap = map(&[(0..5, 10), (10..15, -10)]);
+        assert_eq!(swap.get(3), 13);
+        assert_eq!(swap.get(12), 2);
+        assert_eq!(swap.get(7), 7);
+        assert_eq!(swap.then(&swap), IntervalMap::default());
+        assert_eq!(swap.invert(), Ok(swap.clone()));
+
+        // Rotate 0..21 by one
+        let shift = map(&[(0..20, 1), (20..21, -20)]);
+        let both = swap.then(&shift);
+        for x in -3..25 {
+            assert_eq!(both.get(x), shift.get(swap.get(x)));
+        }
+        let back = both.invert().unwrap();
+        for x in -3..25 {
+            assert_eq!(back.get(both.get(x)), x);
+        }
+        assert!(map(&[(0..5, 1)]).invert().is_err());
+        assert!(IntervalMap::new([
+            Piece {
+                range: 0..5,
+                offset: 1
+            },
+            Piece {
+                range: 4..6,
+                offset: 2
+            },
+        ])
+        .is_err());
+
+        // Touching pieces of one offset join, and images merge in the set
+        assert_eq!(map(&[(0..2, 3), (2..4, 3)]), map(&[(0..4, 3)]));
+        let set = IntervalSet::from_iter([0..5, 5..15]);
+        assert_eq!(swap.apply(&set), set);
+        assert_eq!(
+            shift
+                .apply(&IntervalSet::from_iter([18..20, 20..25]))
+                .ranges(),
+            [0..1, 19..25]
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Analysis of a saved private leaderboard export, works offline on the JSON file.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::json::Json;
+use crate::solution::Res;
+
+/// Puzzles unlock at midnight EST, 05:00 UTC
+const UNLOCK_UTC_HOUR: i64 = 5;
+
+#[derive(Debug)]
+struct Member {
+    name: String,
+    local_score: u64,
+    stars: u64,
+    /// Unix time of each star, by (day, part)
+    times: BTreeMap<(u8, u8), i64>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Board {
+    year: i64,
+    members: Vec<Member>,
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn unlock(year: i64, day: u8) -> i64 {
+    days_from_civil(year, 12, day as i64) * 86400 + UNLOCK_UTC_HOUR * 3600
+}
+
+fn duration(secs: i64) -> String {
+    let (days, rest) = (secs / 86400, secs % 86400);
+    let (h, m, s) = (rest / 3600, rest / 60 % 60, rest % 60);
+    if days > 0 {
+        format!("{days}d {h:02}:{m:02}:{s:02}")
+    } else {
+        format!("{h}:{m:02}:{s:02}")
+    }
+}
+
+fn table(rows: &[Vec<String>]) -> String {
+    let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths = (0..cols)
+        .map(|col| {
+            rows.iter()
+                .filter_map(|row| row.get(col))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let mut out = String::new();
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out += line.trim_end();
+        out.push('\n');
+    }
+    out
+}
+
+impl Board {
+    pub(crate) fn parse(text: &str) -> Res<Self> {
+        let json = Json::parse(text)?;
+        let year = json
+            .get("event")
+            .and_then(Json::as_str)
+            .and_then(|event| event.parse().ok())
+            .ok_or("missing event year")?;
+        let members = json
+            .get("members")
+            .and_then(Json::as_obj)
+            .ok_or("missing members")?;
+
+        let mut out = Vec::with_capacity(members.len());
+        for (id, member) in members {
+            let num = |key| member.get(key).and_then(Json::as_f64).unwrap_or(0.0) as u64;
+            let name = member
+                .get("name")
+                .and_then(Json::as_str)
+                .map_or_else(|| format!("(anonymous #{id})"), str::to_string);
+            let mut times = BTreeMap::new();
+            let days = member.get("completion_day_level").and_then(Json::as_obj);
+            for (day, parts) in days.into_iter().flatten() {
+                let day = day.parse().map_err(|_| format!("invalid day '{day}'"))?;
+                for (part, star) in parts.as_obj().into_iter().flatten() {
+                    let part = part.parse().map_err(|_| format!("invalid part '{part}'"))?;
+                    let ts = star
+                        .get("get_star_ts")
+                        .and_then(Json::as_f64)
+                        .ok_or_else(|| format!("{name}: day {day} part {part} has no time"))?;
+                    times.insert((day, part), ts as i64);
+                }
+            }
+            out.push(Member {
+                name,
+                local_score: num("local_score"),
+                stars: num("stars"),
+                times,
+            });
+        }
+        out.sort_by(|a, b| {
+            (b.local_score, b.stars, &a.name).cmp(&(a.local_score, a.stars, &b.name))
+        });
+        Ok(Self { year, members: out })
+    }
+
+    /// Local points of every star, by (day, part) then member index
+    fn points(&self) -> BTreeMap<(u8, u8), Vec<(usize, u64)>> {
+        let mut stars = BTreeMap::<_, Vec<_>>::new();
+        for (idx, member) in self.members.iter().enumerate() {
+            for (&key, &ts) in &member.times {
+                stars.entry(key).or_default().push((ts, idx));
+            }
+        }
+        let count = self.members.len() as u64;
+        stars
+            .into_iter()
+            .map(|(key, mut got)| {
+                got.sort();
+                let points = (0..)
+                    .zip(got)
+                    .map(|(rank, (_, idx))| (idx, count - rank))
+                    .collect();
+                (key, points)
+            })
+            .collect()
+    }
+
+    fn days(&self) -> Vec<u8> {
+        let mut days = self
+            .members
+            .iter()
+            .flat_map(|member| member.times.keys().map(|(day, _)| *day))
+            .collect::<Vec<_>>();
+        days.sort();
+        days.dedup();
+        days
+    }
+
+    /// Rank table, star times, part gaps and daily point progression
+    pub(crate) fn report(&self) -> String {
+        let mut out = format!("Leaderboard {}\n\n", self.year);
+
+        let mut rows = vec![["rank", "name", "score", "stars"]
+            .map(String::from)
+            .to_vec()];
+        for (rank, member) in (1..).zip(&self.members) {
+            rows.push(vec![
+                rank.to_string(),
+                member.name.clone(),
+                member.local_score.to_string(),
+                member.stars.to_string(),
+            ]);
+        }
+        out += &table(&rows);
+
+        out += "\nTime from unlock to each star\n";
+        let mut rows = vec![["day", "name", "part1", "part2", "gap"]
+            .map(String::from)
+            .to_vec()];
+        for day in self.days() {
+            let start = unlock(self.year, day);
+            let mut got = self
+                .members
+                .iter()
+                .filter_map(|member| {
+                    let p1 = member.times.get(&(day, 1))?;
+                    Some((p1, member.times.get(&(day, 2)), &member.name))
+                })
+                .collect::<Vec<_>>();
+            got.sort_by_key(|(p1, p2, _)| (p2.is_none(), p2.copied(), **p1));
+            for (p1, p2, name) in got {
+                rows.push(vec![
+                    format!("{day:02}"),
+                    name.clone(),
+                    duration(p1 - start),
+                    p2.map_or("-".to_string(), |p2| duration(p2 - start)),
+                    p2.map_or("-".to_string(), |p2| duration(p2 - p1)),
+                ]);
+            }
+        }
+        out += &table(&rows);
+
+        out += "\nPoints after each day\n";
+        let days = self.days();
+        let mut header = vec!["name".to_string()];
+        header.extend(days.iter().map(|day| format!("{day:02}")));
+        let mut rows = vec![header];
+        let mut totals = vec![0; self.members.len()];
+        let mut columns = vec![Vec::new(); self.members.len()];
+        let points = self.points();
+        for &day in &days {
+            for part in 1..=2 {
+                for &(idx, score) in points.get(&(day, part)).into_iter().flatten() {
+                    totals[idx] += score;
+                }
+            }
+            for (column, total) in columns.iter_mut().zip(&totals) {
+                column.push(total.to_string());
+            }
+        }
+        for (member, column) in self.members.iter().zip(columns) {
+            let mut row = vec![member.name.clone()];
+            row.extend(column);
+            rows.push(row);
+        }
+        out += &table(&rows);
+        out
+    }
+
+    /// One line per star: member, day, part, unix time, seconds since unlock and points
+    pub(crate) fn csv(&self) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+        let mut out = String::from("member,day,part,timestamp,since_unlock,points\n");
+        for ((day, part), got) in self.points() {
+            for (idx, points) in got {
+                let member = &self.members[idx];
+                let ts = member.times[&(day, part)];
+                let since = ts - unlock(self.year, day);
+                let name = quote(&member.name);
+                writeln!(out, "{name},{day},{part},{ts},{since},{points}").unwrap();
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoring() {
+        // Day 1 of 2023 unlocked at 1701406800
+        let text = r#"{"event":"2023","owner_id":1,"members":{
+            "1":{"id":1,"name":"ann","stars":2,"local_score":4,"completion_day_level":
+                {"1":{"1":{"get_star_ts":1701406900},"2":{"get_star_ts":1701407000}}}},
+            "2":{"id":2,"name":null,"stars":1,"local_score":1,"completion_day_level":
+                {"1":{"1":{"get_star_ts":1701406950}}}}}}"#;
+        let board = Board::parse(text).unwrap();
+        assert_eq!(unlock(2023, 1), 1701406800);
+        assert_eq!(board.members[1].name, "(anonymous #2)");
+        assert_eq!(board.points()[&(1, 1)], [(0, 2), (1, 1)]);
+        assert_eq!(
+            board.csv().lines().nth(1),
+            Some("\"ann\",1,1,1701406900,100,2")
+        );
+        assert!(board.report().contains("0:01:40  0:03:20  0:01:40"));
+    }
+}
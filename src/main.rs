@@ -1,4 +1,11 @@
 // __ //
+mod anonymise;
+mod bigint;
+mod bitgrid;
+mod checked;
+mod config;
+mod coord;
+mod cycle;
 mod day01;
 mod day02;
 mod day03;
@@ -13,17 +20,243 @@ mod day11;
 mod day12;
 mod day13;
 mod day14;
+mod explore;
+mod geometry;
+mod grid;
+mod import;
+mod interval;
+mod json;
+mod leaderboard;
+mod manifest;
+mod matcher;
+mod math;
+mod runner;
+mod search;
+mod solution;
+mod status;
+mod store;
+
+use config::Config;
+use solution::Res;
 
 #[allow(unused)]
 pub(crate) fn open_first(paths: &[&str]) -> std::io::Result<String> {
-    let Some(path) = paths.iter().find(|path| std::fs::metadata(path).is_ok()) else {
+    let Some(path) = paths.iter().find(|path| store::exists(path)) else {
         return Err(std::io::Error::other(format!(
             "No valid path in list: {paths:?}",
         )));
     };
-    std::fs::read_to_string(path)
+    store::read(path)
+}
+
+const USAGE: &str = "\
+usage: aoc-2023 [FLAGS] COMMAND
+commands:
+  run [DAY|all] [PART] [--KEY VALUE]..
+                          solve parts on their inputs, with settings of the day
+  explore DAY [PATH]      step through a simulation
+  render DAY [PATH] [--html OUT]
+                          draw a schematic with its parts and gears highlighted
+  config show             print the effective settings
+  variants                alternative rules, picked with --variant NAME after run DAY PART
+  status                  calendar of solved parts, inputs and checked answers
+  inputs encrypt          encrypt the configured input of every day in place
+  import DAY HTML [--force]
+                          copy samples and answers from a saved puzzle page
+  anonymise DAY [PATH] [--seed N]
+                          print a scrambled copy of an input
+  leaderboard FILE [--csv OUT]
+                          analyse a saved private leaderboard export
+flags:
+  --input PATH, --sample PATH, --verbosity N, -v, --threads N,
+  --session PATH, --time-limit SECS, --format text|json, --checked, --explain,
+  --dayNN.KEY VALUE       a setting of a single day, like --day02.bag \"red=12, green=13\"";
+
+fn parse_day(day: &str) -> Res<u8> {
+    day.parse::<u8>()
+        .map_err(|e| format!("invalid day '{day}': {e}"))
+}
+
+fn explore(config: &Config, day: u8, path: Option<&str>) -> Res<()> {
+    let path = match path {
+        Some(path) => path.into(),
+        None => runner::resolve_input(config, day)?,
+    };
+    let text = store::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+
+    let stdin = std::io::stdin().lock();
+    let stdout = std::io::stdout().lock();
+    match day {
+        5 => explore::explore(day05::Stages::parse(&text)?, stdin, stdout),
+        8 => explore::explore(day08::Walk::parse(&text)?, stdin, stdout),
+        10 => explore::explore(day10::Walkers::parse(&text)?, stdin, stdout),
+        14 => explore::explore(day14::Rolling::parse(&text)?, stdin, stdout),
+        _ => return Err(format!("day {day} has no simulation")),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn render(config: &Config, day: u8, path: Option<&str>, html: Option<&str>) -> Res<()> {
+    let path = match path {
+        Some(path) => path.into(),
+        None => runner::resolve_input(config, day)?,
+    };
+    let text = store::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    match (day, html) {
+        (3, None) => print!("{}", day03::render(&text)?),
+        (3, Some(out)) => {
+            std::fs::write(out, day03::render_html(&text)?).map_err(|e| format!("{out}: {e}"))?;
+            eprintln!("wrote {out}");
+        }
+        _ => return Err(format!("day {day} has no renderer")),
+    }
+    Ok(())
+}
+
+fn anonymise(config: &Config, day: u8, args: &[&str]) -> Res<()> {
+    let (path, seed) = match args {
+        [] => (None, None),
+        ["--seed", seed] => (None, Some(seed)),
+        [path] => (Some(path), None),
+        [path, "--seed", seed] => (Some(path), Some(seed)),
+        _ => return Err(USAGE.to_string()),
+    };
+    let path = match path {
+        Some(path) => path.into(),
+        None => runner::resolve_input(config, day)?,
+    };
+    let seed = match seed {
+        Some(seed) => seed
+            .parse::<u64>()
+            .map_err(|e| format!("invalid seed '{seed}': {e}"))?,
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64),
+    };
+    let text = store::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    print!("{}", anonymise::anonymise(day, &text, seed)?);
+    eprintln!("anonymised {} with --seed {seed}", path.display());
+    Ok(())
+}
+
+fn import(config: &Config, day: u8, html: &str, force: bool) -> Res<()> {
+    let input = config.day_path("input", day);
+    let dir = input.parent().unwrap_or(std::path::Path::new("."));
+    std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let manifest = &config::root().join(manifest::FILE_NAME);
+    for line in import::import(day, html.as_ref(), dir, manifest, force)? {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn leaderboard(path: &str, csv: Option<&str>) -> Res<()> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let board = leaderboard::Board::parse(&text).map_err(|e| format!("{path}: {e}"))?;
+    print!("{}", board.report());
+    if let Some(out) = csv {
+        std::fs::write(out, board.csv()).map_err(|e| format!("{out}: {e}"))?;
+        eprintln!("wrote {out}");
+    }
+    Ok(())
+}
+
+/// Trailing `--key value` pairs of `run DAY PART`, as settings of that day
+fn day_flags(config: &mut Config, day: u8, flags: &[&str]) -> Res<()> {
+    let mut iter = flags.iter();
+    while let Some(flag) = iter.next() {
+        let name = flag
+            .strip_prefix("--")
+            .ok_or_else(|| format!("unexpected argument '{flag}'\n{USAGE}"))?;
+        let (name, value) = match name.split_once('=') {
+            Some((name, value)) => (name, value),
+            None => (
+                name,
+                *iter
+                    .next()
+                    .ok_or_else(|| format!("missing value for {flag}"))?,
+            ),
+        };
+        let key = format!("day{day:02}.{}", name.replace('-', "_"));
+        config.set(&key, value.to_string());
+    }
+    Ok(())
+}
+
+fn command(mut args: Vec<String>) -> Res<()> {
+    let mut config = Config::load(&mut args)?;
+    checked::set_enabled(config.parse::<bool>("checked")?);
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args[..] {
+        ["run"] | ["run", "all"] => runner::run(&config, None, None),
+        ["run", day] => runner::run(&config, Some(parse_day(day)?), None),
+        ["run", day, part, ref flags @ ..] => {
+            let day = parse_day(day)?;
+            let part = part
+                .parse::<u8>()
+                .map_err(|e| format!("invalid part '{part}': {e}"))?;
+            day_flags(&mut config, day, flags)?;
+            runner::run(&config, Some(day), Some(part))
+        }
+        ["explore", day] => explore(&config, parse_day(day)?, None),
+        ["explore", day, path] => explore(&config, parse_day(day)?, Some(path)),
+        ["render", day] => render(&config, parse_day(day)?, None, None),
+        ["render", day, path] => render(&config, parse_day(day)?, Some(path), None),
+        ["render", day, "--html", out] => render(&config, parse_day(day)?, None, Some(out)),
+        ["render", day, path, "--html", out] => {
+            render(&config, parse_day(day)?, Some(path), Some(out))
+        }
+        ["variants"] => {
+            for solution in solution::SOLUTIONS {
+                for variant in solution.variants() {
+                    println!(
+                        "day{:02} part{} {:10} {}",
+                        solution.day(),
+                        variant.part,
+                        variant.name,
+                        variant.help
+                    );
+                }
+            }
+            Ok(())
+        }
+        ["status"] => {
+            print!("{}", status::status(&config)?);
+            Ok(())
+        }
+        ["config", "show"] => {
+            print!("{}", config.show());
+            Ok(())
+        }
+        ["import", day, html] => import(&config, parse_day(day)?, html, false),
+        ["import", day, html, "--force"] => import(&config, parse_day(day)?, html, true),
+        ["anonymise", day, ref rest @ ..] => anonymise(&config, parse_day(day)?, rest),
+        ["leaderboard", path] => leaderboard(path, None),
+        ["leaderboard", path, "--csv", out] => leaderboard(path, Some(out)),
+        ["inputs", "encrypt"] => {
+            let inputs = solution::SOLUTIONS
+                .iter()
+                .map(|solution| config.day_path("input", solution.day()))
+                .collect::<Vec<_>>();
+            let done = store::encrypt_inputs(&inputs).map_err(|e| e.to_string())?;
+            for path in &done {
+                println!("encrypted {}", path.display());
+            }
+            println!(
+                "{} input(s) encrypted, key in {}",
+                done.len(),
+                store::key_path().display()
+            );
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
 }
 
 fn main() {
-    println!("Hello, world!");
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Err(err) = command(args) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
 }
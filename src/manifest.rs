@@ -0,0 +1,76 @@
+//! Expected answers, kept in `answers.toml` next to the config.
+//!
+//! ```toml
+//! [day01]
+//! part1.sample = "142"
+//! part1.input = "55029"
+//! ```
+//!
+//! Keys name the part and the stem of the input file the answer belongs to.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::parse_toml;
+use crate::solution::Res;
+
+pub(crate) const FILE_NAME: &str = "answers.toml";
+
+#[derive(Debug, Default)]
+pub(crate) struct Manifest {
+    answers: BTreeMap<(u8, u8, String), String>,
+}
+
+impl Manifest {
+    pub(crate) fn parse(text: &str) -> Res<Self> {
+        let mut answers = BTreeMap::new();
+        for (key, value, line) in parse_toml(text)? {
+            let entry = key.split_once('.').and_then(|(day, rest)| {
+                let day = day.strip_prefix("day")?.parse().ok()?;
+                let (part, input) = rest.split_once('.')?;
+                let part = part.strip_prefix("part")?.parse().ok()?;
+                Some((day, part, input.to_string()))
+            });
+            let entry = entry.ok_or_else(|| format!("line {line}: expected dayNN.partN.input"))?;
+            answers.insert(entry, value);
+        }
+        Ok(Self { answers })
+    }
+
+    /// Load the manifest, a missing file is an empty manifest
+    pub(crate) fn load(path: &Path) -> Res<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {e}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("{}: {err}", path.display())),
+        }
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Res<()> {
+        let mut out = String::new();
+        let mut section = 0;
+        for ((day, part, input), answer) in &self.answers {
+            if *day != section {
+                if section != 0 {
+                    out.push('\n');
+                }
+                out += &format!("[day{day:02}]\n");
+                section = *day;
+            }
+            out += &format!("part{part}.{input} = {answer:?}\n");
+        }
+        std::fs::write(path, out).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Every input with an expected answer for the part, as (file stem, answer)
+    pub(crate) fn inputs(&self, day: u8, part: u8) -> impl Iterator<Item = (&str, &str)> {
+        self.answers
+            .range((day, part, String::new())..(day, part + 1, String::new()))
+            .map(|((_, _, input), answer)| (input.as_str(), answer.as_str()))
+    }
+
+    pub(crate) fn insert(&mut self, day: u8, part: u8, input: &str, answer: &str) {
+        self.answers
+            .insert((day, part, input.to_string()), answer.to_string());
+    }
+}
@@ -0,0 +1,158 @@
+//! Multi-pattern matcher in the style of Aho–Corasick.
+//!
+//! The patterns form a byte trie, with failure links to the longest proper
+//! suffix that is also in the trie. A single pass over the text then finds
+//! every occurrence of every pattern, overlapping ones included.
+
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Default)]
+struct Node {
+    next: HashMap<u8, usize>,
+    fail: usize,
+    /// Pattern ending at this node
+    output: Option<usize>,
+    /// Nearest node down the failure chain with an output
+    dict: Option<usize>,
+}
+
+/// Finds the patterns of a vocabulary, each mapped to a value
+#[derive(Debug)]
+pub(crate) struct Matcher<V> {
+    nodes: Vec<Node>,
+    /// Length and value of each pattern
+    patterns: Vec<(usize, V)>,
+}
+
+/// An occurrence of a pattern at `start..end` of the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Match<'m, V> {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) value: &'m V,
+}
+
+impl<V> Matcher<V> {
+    /// Matcher of `vocabulary`, empty patterns never match and a repeated one keeps its last value
+    pub(crate) fn new<S: AsRef<str>>(vocabulary: impl IntoIterator<Item = (S, V)>) -> Self {
+        let mut nodes = vec![Node::default()];
+        let mut patterns = Vec::new();
+        for (word, value) in vocabulary {
+            let word = word.as_ref().as_bytes();
+            if word.is_empty() {
+                continue;
+            }
+            let mut at = 0;
+            for &byte in word {
+                at = match nodes[at].next.get(&byte) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[at].next.insert(byte, next);
+                        next
+                    }
+                };
+            }
+            nodes[at].output = Some(patterns.len());
+            patterns.push((word.len(), value));
+        }
+
+        // Breadth first, so every shorter suffix has its links already
+        let mut queue = nodes[0].next.values().copied().collect::<VecDeque<_>>();
+        while let Some(at) = queue.pop_front() {
+            let edges = nodes[at]
+                .next
+                .iter()
+                .map(|(&byte, &next)| (byte, next))
+                .collect::<Vec<_>>();
+            for (byte, next) in edges {
+                let mut fail = nodes[at].fail;
+                while fail != 0 && !nodes[fail].next.contains_key(&byte) {
+                    fail = nodes[fail].fail;
+                }
+                let fail = match nodes[fail].next.get(&byte) {
+                    Some(&target) if target != next => target,
+                    _ => 0,
+                };
+                nodes[next].fail = fail;
+                nodes[next].dict = match nodes[fail].output {
+                    Some(_) => Some(fail),
+                    None => nodes[fail].dict,
+                };
+                queue.push_back(next);
+            }
+        }
+
+        Self { nodes, patterns }
+    }
+
+    /// Every match in `text`, by end position, the longest first for a shared end
+    pub(crate) fn find_iter<'m, 't>(&'m self, text: &'t str) -> Matches<'m, 't, V> {
+        Matches {
+            matcher: self,
+            text: text.as_bytes(),
+            pos: 0,
+            state: 0,
+            pending: None,
+        }
+    }
+}
+
+pub(crate) struct Matches<'m, 't, V> {
+    matcher: &'m Matcher<V>,
+    text: &'t [u8],
+    pos: usize,
+    state: usize,
+    /// Next node of the current position with an output to report
+    pending: Option<usize>,
+}
+
+impl<'m, V> Iterator for Matches<'m, '_, V> {
+    type Item = Match<'m, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nodes = &self.matcher.nodes;
+        while self.pending.is_none() {
+            let &byte = self.text.get(self.pos)?;
+            self.pos += 1;
+            let mut state = self.state;
+            while state != 0 && !nodes[state].next.contains_key(&byte) {
+                state = nodes[state].fail;
+            }
+            self.state = nodes[state].next.get(&byte).copied().unwrap_or(0);
+            self.pending = match nodes[self.state].output {
+                Some(_) => Some(self.state),
+                None => nodes[self.state].dict,
+            };
+        }
+        let node = self.pending?;
+        self.pending = nodes[node].dict;
+        let (len, value) = &self.matcher.patterns[nodes[node].output?];
+        Some(Match {
+            start: self.pos - len,
+            end: self.pos,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping() {
+        let matcher = Matcher::new([("he", 1), ("she", 2), ("his", 3), ("hers", 4)]);
+        let found = matcher
+            .find_iter("ushers")
+            .map(|m| (m.start, m.end, *m.value))
+            .collect::<Vec<_>>();
+        assert_eq!(found, [(1, 4, 2), (2, 4, 1), (2, 6, 4)]);
+
+        let matcher = Matcher::new([("a", 'a'), ("aa", 'b'), ("", 'c')]);
+        assert_eq!(matcher.find_iter("aaa").count(), 5);
+        assert_eq!(matcher.find_iter("").count(), 0);
+        assert_eq!(matcher.find_iter("bab").count(), 1);
+    }
+}
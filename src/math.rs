@@ -0,0 +1,134 @@
+//! Number theory helpers: gcd, lcm, the Chinese remainder theorem and modular products.
+
+use crate::solution::Res;
+
+/// Greatest common divisor, never negative
+pub(crate) fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i128
+}
+
+/// Least common multiple of all values, 1 when there are none and `None` on overflow
+pub(crate) fn lcm(values: impl IntoIterator<Item = i128>) -> Option<i128> {
+    values.into_iter().try_fold(1, |acc: i128, value| {
+        if acc == 0 || value == 0 {
+            return Some(0);
+        }
+        (acc / gcd(acc, value)).checked_mul(value)?.checked_abs()
+    })
+}
+
+/// Extended Euclid, `(g, x, y)` with `a * x + b * y == g == gcd(a, b)`
+pub(crate) fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    let (mut y0, mut y1) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 {
+        (-r0, -x0, -y0)
+    } else {
+        (r0, x0, y0)
+    }
+}
+
+/// `a * b mod m` in `0..m` for any `m > 0`, without intermediate overflow
+pub(crate) fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    assert!(m > 0, "modulus must be positive");
+    let (mut a, mut b) = (a.rem_euclid(m), b.rem_euclid(m));
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+    // Double and add, every partial value stays below 2 * m
+    let mut out = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            out = add_mod(out, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    out
+}
+
+fn add_mod(a: i128, b: i128, m: i128) -> i128 {
+    // a and b are in 0..m, so m - b never overflows
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// Solve `x ≡ r (mod m)` for every `(r, m)`, moduli need not be coprime.
+///
+/// Returns `(x, lcm)` with `x` the smallest non-negative solution; all
+/// solutions are `x + k * lcm`. Fails if the congruences contradict each
+/// other or the combined modulus does not fit an `i128`.
+pub(crate) fn crt(congruences: impl IntoIterator<Item = (i128, i128)>) -> Res<(i128, i128)> {
+    let (mut x, mut m) = (0, 1);
+    for (r, n) in congruences {
+        if n <= 0 {
+            return Err(format!("crt: modulus {n} is not positive"));
+        }
+        let r = r.rem_euclid(n);
+        let (g, p, _) = ext_gcd(m, n);
+        let diff = r - x;
+        if diff % g != 0 {
+            return Err(format!(
+                "crt: x ≡ {x} (mod {m}) and x ≡ {r} (mod {n}) have no common solution"
+            ));
+        }
+        let step = n / g;
+        let joint =
+            lcm([m, n]).ok_or_else(|| format!("crt: combined modulus of {m} and {n} overflows"))?;
+        // x + m * t with t ≡ p * diff / g (mod n / g)
+        let t = mul_mod(p, diff / g, step);
+        x = (x + mul_mod(m, t, joint)).rem_euclid(joint);
+        m = joint;
+    }
+    Ok((x, m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn euclid() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd(-4, 6), 2);
+        assert_eq!(lcm([4, 6, 10]), Some(60));
+        assert_eq!(lcm([]), Some(1));
+        assert_eq!(lcm([i128::MAX, i128::MAX - 1]), None);
+        let (g, x, y) = ext_gcd(240, 46);
+        assert_eq!((g, 240 * x + 46 * y), (2, 2));
+    }
+
+    #[test]
+    fn modular() {
+        assert_eq!(mul_mod(-3, 5, 7), 6);
+        let m = i128::MAX - 1;
+        assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+        assert_eq!(mul_mod(1 << 100, 1 << 100, i128::MAX), 1 << 73);
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        assert_eq!(crt([(3, 4), (1, 6)]), Ok((7, 12)));
+        assert_eq!(crt([(0, 3), (0, 3)]), Ok((0, 3)));
+        assert_eq!(crt([]), Ok((0, 1)));
+        let err = crt([(3, 4), (2, 6)]).unwrap_err();
+        assert!(err.contains("no common solution"), "{err}");
+        assert!(crt([(0, 1 << 100), (1, (1 << 100) + 1)]).is_err());
+    }
+}
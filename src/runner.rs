@@ -0,0 +1,251 @@
+//! Runs registered solutions on their inputs and reports answers.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::solution::{self, Answer, Res, Settings, Solution};
+use crate::store;
+
+/// Every `sample*.txt` in `dir`, sorted by name
+pub(crate) fn samples(dir: &Path) -> Vec<PathBuf> {
+    let mut samples = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("sample") && name.ends_with(".txt"))
+        })
+        .collect::<Vec<_>>();
+    samples.sort();
+    samples
+}
+
+/// Input of a day: the configured input, then the preferred sample, then any other sample
+pub(crate) fn resolve_input(config: &Config, day: u8) -> Res<PathBuf> {
+    let input = config.day_path("input", day);
+    if store::exists(&input) {
+        return Ok(input);
+    }
+    let sample = config.day_path("sample", day);
+    if sample.is_file() {
+        return Ok(sample);
+    }
+    let dir = sample
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    samples(dir).into_iter().next().ok_or_else(|| {
+        format!(
+            "day {day} has no input, tried {} and {}",
+            input.display(),
+            sample.display()
+        )
+    })
+}
+
+struct Outcome {
+    day: u8,
+    part: u8,
+    input: PathBuf,
+    answer: Res<Answer>,
+    elapsed: Duration,
+    /// Report asked for with `explain`, if the day has one
+    explanation: Option<Res<String>>,
+}
+
+fn run_one(
+    solution: &'static dyn Solution,
+    part: u8,
+    input: &str,
+    settings: Settings,
+    limit: Duration,
+    explain: bool,
+) -> Outcome {
+    let day = solution.day();
+    let input = PathBuf::from(input);
+    let text = match store::read(&input) {
+        Ok(text) => text,
+        Err(err) => {
+            return Outcome {
+                day,
+                part,
+                answer: Err(format!("{}: {err}", input.display())),
+                input,
+                elapsed: Duration::ZERO,
+                explanation: None,
+            }
+        }
+    };
+
+    // Solve on a detached thread, so a part running past the limit can be abandoned
+    let (send, recv) = mpsc::channel();
+    let start = Instant::now();
+    std::thread::spawn(move || {
+        let answer = solution.solve_with(part, &text, &settings);
+        let explanation = explain
+            .then(|| solution.explain(part, &text, &settings))
+            .flatten();
+        let _ = send.send((answer, explanation));
+    });
+    let answer = if limit.is_zero() {
+        recv.recv().map_err(|_| "panicked".to_string())
+    } else {
+        recv.recv_timeout(limit).map_err(|err| match err {
+            mpsc::RecvTimeoutError::Timeout => format!("timed out after {limit:?}"),
+            mpsc::RecvTimeoutError::Disconnected => "panicked".to_string(),
+        })
+    };
+    let elapsed = start.elapsed();
+    let (answer, explanation) = match answer {
+        Ok((answer, explanation)) => (answer, explanation),
+        Err(err) => (Err(err), None),
+    };
+    Outcome {
+        day,
+        part,
+        input,
+        answer,
+        elapsed,
+        explanation,
+    }
+}
+
+/// Solve a single part on the file at `input` with default settings, within the time limit
+pub(crate) fn solve_file(
+    solution: &'static dyn Solution,
+    part: u8,
+    input: &Path,
+    limit: Duration,
+) -> Res<Answer> {
+    let input = input.display().to_string();
+    run_one(solution, part, &input, Settings::default(), limit, false).answer
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            c if c.is_control() => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn print(outcomes: &[Outcome], config: &Config) -> Res<()> {
+    let verbosity = config.parse::<u8>("verbosity")?;
+    let explain = config.parse::<bool>("explain")?;
+    match config.get("format") {
+        Some("json") => {
+            let items = outcomes
+                .iter()
+                .map(|out| {
+                    let (answer, error) = match &out.answer {
+                        Ok(answer) => (json_string(&answer.to_string()), "null".to_string()),
+                        Err(err) => ("null".to_string(), json_string(err)),
+                    };
+                    let explanation = match &out.explanation {
+                        Some(Ok(report)) => json_string(report),
+                        _ => "null".to_string(),
+                    };
+                    format!(
+                        "{{\"day\":{},\"part\":{},\"input\":{},\"answer\":{},\"error\":{},\"explain\":{},\"ms\":{:.3}}}",
+                        out.day,
+                        out.part,
+                        json_string(&out.input.display().to_string()),
+                        answer,
+                        error,
+                        explanation,
+                        out.elapsed.as_secs_f64() * 1000.0,
+                    )
+                })
+                .collect::<Vec<_>>();
+            println!("[{}]", items.join(","));
+        }
+        Some("text") => {
+            for out in outcomes {
+                let mut line = format!("day{:02} part{}: ", out.day, out.part);
+                match &out.answer {
+                    Ok(answer) => line += &answer.to_string(),
+                    Err(err) => line += &format!("error: {err}"),
+                }
+                if verbosity >= 1 {
+                    line += &format!("  ({:.2?})", out.elapsed);
+                }
+                if verbosity >= 2 {
+                    line += &format!("  {}", out.input.display());
+                }
+                println!("{line}");
+                match &out.explanation {
+                    Some(Ok(report)) => print!("{report}"),
+                    Some(Err(err)) => println!("cannot explain: {err}"),
+                    None if explain => println!("nothing to explain"),
+                    None => (),
+                }
+            }
+        }
+        format => return Err(format!("unknown format {format:?}, expected text or json")),
+    }
+    Ok(())
+}
+
+/// Run `part` (or every implemented part) of `day` (or every registered day)
+pub(crate) fn run(config: &Config, day: Option<u8>, part: Option<u8>) -> Res<()> {
+    let solutions = match day {
+        Some(day) => vec![solution::find(day).ok_or_else(|| format!("day {day} is not solved"))?],
+        None => solution::SOLUTIONS.to_vec(),
+    };
+    let mut jobs = Vec::new();
+    for solution in solutions {
+        let input = resolve_input(config, solution.day())?;
+        let input = input.display().to_string();
+        match part {
+            Some(part) => jobs.push((solution, part, input)),
+            None => jobs.extend((1..=solution.parts()).map(|part| (solution, part, input.clone()))),
+        }
+    }
+
+    let limit = config.time_limit()?;
+    let explain = config.parse::<bool>("explain")?;
+    let threads = config
+        .parse::<usize>("threads")?
+        .clamp(1, jobs.len().max(1));
+    let next = AtomicUsize::new(0);
+    let mut outcomes = std::thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while let Some((solution, part, input)) =
+                        jobs.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        let settings = config.section(solution.day());
+                        done.push(run_one(*solution, *part, input, settings, limit, explain));
+                    }
+                    done
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("worker panicked"))
+            .collect::<Vec<_>>()
+    });
+    outcomes.sort_by_key(|out| (out.day, out.part));
+
+    print(&outcomes, config)?;
+    let failed = outcomes.iter().filter(|out| out.answer.is_err()).count();
+    if failed > 0 {
+        return Err(format!("{failed} part(s) failed"));
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+//! Graph searches over a neighbour function: BFS, Dijkstra and A*.
+//!
+//! Nodes are any `Clone + Eq + Hash` state, so a search can carry more than a
+//! position, like the heading and run length of a crucible. A search stops at
+//! the first node passing `goal`, or explores everything reachable when the
+//! goal never matches, leaving a full distance map.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::coord::Pos;
+use crate::grid::Grid;
+
+/// Distances of every settled node and the tree of shortest paths to them
+#[derive(Debug)]
+pub(crate) struct Search<N, C> {
+    dist: HashMap<N, C>,
+    prev: HashMap<N, N>,
+    goal: Option<N>,
+}
+
+impl<N: Clone + Eq + Hash, C: Copy> Search<N, C> {
+    // Day10 reads all distances at once
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn distance(&self, node: &N) -> Option<C> {
+        self.dist.get(node).copied()
+    }
+
+    pub(crate) fn distances(&self) -> &HashMap<N, C> {
+        &self.dist
+    }
+
+    /// First node found passing the goal, if any
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn goal(&self) -> Option<&N> {
+        self.goal.as_ref()
+    }
+
+    /// Shortest path from the start to `to`, both included
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn path(&self, to: &N) -> Option<Vec<N>> {
+        self.dist.get(to)?;
+        let mut path = vec![to.clone()];
+        while let Some(prev) = self.prev.get(path.last().unwrap()) {
+            path.push(prev.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Breadth first, every edge costs one
+pub(crate) fn bfs<N, I>(
+    start: N,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Search<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut search = Search {
+        dist: HashMap::from([(start.clone(), 0)]),
+        prev: HashMap::new(),
+        goal: None,
+    };
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        if goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+        let dist = search.dist[&node] + 1;
+        for next in neighbours(&node) {
+            if !search.dist.contains_key(&next) {
+                search.dist.insert(next.clone(), dist);
+                search.prev.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    search
+}
+
+/// Cheapest paths with non-negative edge costs
+// Weighted searches for later days, day10 only needs bfs
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn dijkstra<N, C, I>(
+    start: N,
+    neighbours: impl FnMut(&N) -> I,
+    goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, neighbours, |_| C::default(), goal)
+}
+
+/// Dijkstra guided by `heuristic`, which must never overestimate the cost to the goal
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn astar<N, C, I>(
+    start: N,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut search = Search {
+        dist: HashMap::new(),
+        prev: HashMap::new(),
+        goal: None,
+    };
+    // Best known cost and predecessor of nodes not settled yet
+    let mut open = HashMap::from([(start.clone(), (C::default(), None::<N>))]);
+    // Nodes live in `seen`, the heap orders their indices
+    let mut seen = vec![start.clone()];
+    let mut heap = BinaryHeap::from([(Reverse(heuristic(&start)), Reverse(C::default()), 0)]);
+
+    while let Some((_, Reverse(cost), idx)) = heap.pop() {
+        let node = &seen[idx];
+        if search.dist.contains_key(node) || open.get(node).is_some_and(|o| o.0 < cost) {
+            continue;
+        }
+        let node = node.clone();
+        let (_, prev) = open.remove(&node).expect("queued node is open");
+        search.dist.insert(node.clone(), cost);
+        if let Some(prev) = prev {
+            search.prev.insert(node.clone(), prev);
+        }
+        if goal(&node) {
+            search.goal = Some(node);
+            break;
+        }
+        for (next, step) in neighbours(&node) {
+            let cost = cost + step;
+            if search.dist.contains_key(&next) || open.get(&next).is_some_and(|o| o.0 <= cost) {
+                continue;
+            }
+            open.insert(next.clone(), (cost, Some(node.clone())));
+            heap.push((Reverse(cost + heuristic(&next)), Reverse(cost), seen.len()));
+            seen.push(next);
+        }
+    }
+    search
+}
+
+/// Breadth first over the cells of `grid` that `passable` lets through
+// No day searches a plain grid yet
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn grid_bfs<T>(
+    grid: &Grid<T>,
+    start: Pos,
+    mut passable: impl FnMut(&T) -> bool,
+    goal: impl FnMut(&Pos) -> bool,
+) -> Search<Pos, usize> {
+    bfs(
+        start,
+        |&pos| {
+            grid.neighbours(pos)
+                .filter(|&next| passable(&grid[next]))
+                .collect::<Vec<_>>()
+        },
+        goal,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: &str = "\
+S..#....
+.#.#.##.
+.#...#..
+.####.#.
+......#E";
+
+    #[test]
+    fn maze() {
+        let grid = Grid::parse(MAZE, Some).unwrap();
+        let start = grid.position(|&c| c == 'S').unwrap();
+        let end = grid.position(|&c| c == 'E').unwrap();
+
+        let found = grid_bfs(&grid, start, |&c| c != '#', |&pos| pos == end);
+        assert_eq!(found.goal(), Some(&end));
+        assert_eq!(found.distance(&end), Some(15));
+        let path = found.path(&end).unwrap();
+        assert_eq!(path.len(), 16);
+        assert!(path
+            .windows(2)
+            .all(|w| w[0].x.abs_diff(w[1].x) + w[0].y.abs_diff(w[1].y) == 1));
+
+        let everything = grid_bfs(&grid, start, |&c| c != '#', |_| false);
+        assert_eq!(everything.goal(), None);
+        assert_eq!(everything.distances().len(), 27);
+
+        let step = |&pos: &Pos| {
+            grid.neighbours(pos)
+                .filter(|&next| grid[next] != '#')
+                .map(|next| (next, 1))
+                .collect::<Vec<_>>()
+        };
+        let manhattan = |pos: &Pos| pos.x.abs_diff(end.x) + pos.y.abs_diff(end.y);
+        let astar = astar(start, step, manhattan, |&pos| pos == end);
+        assert_eq!(astar.distance(&end), Some(15));
+        let dijkstra = dijkstra(start, step, |&pos| pos == end);
+        assert_eq!(dijkstra.distance(&end), Some(15));
+    }
+
+    #[test]
+    fn weighted() {
+        // The direct edge is dearer than the detour
+        let edges = |&node: &u8| match node {
+            0 => vec![(1, 1), (2, 10)],
+            1 => vec![(2, 3)],
+            _ => vec![],
+        };
+        let found = dijkstra(0, edges, |_| false);
+        assert_eq!(found.distance(&2), Some(4));
+        assert_eq!(found.path(&2), Some(vec![0, 1, 2]));
+        assert_eq!(found.path(&3), None);
+    }
+}
@@ -0,0 +1,172 @@
+//! Common interface of the daily solutions and the registry the runner works from.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::bigint::BigInt;
+
+pub(crate) type Res<T> = Result<T, String>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Answer {
+    Num(i128),
+    Big(BigInt),
+}
+
+macro_rules! answer_from {
+    ($($typ:ty),*) => {
+        $(impl From<$typ> for Answer {
+            fn from(value: $typ) -> Self {
+                Self::Num(value as i128)
+            }
+        })*
+    };
+}
+
+answer_from!(u32, i32, u64, i64, usize, isize, i128);
+
+impl From<BigInt> for Answer {
+    fn from(value: BigInt) -> Self {
+        Self::Big(value)
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Num(num) => write!(f, "{num}"),
+            Self::Big(num) => write!(f, "{num}"),
+        }
+    }
+}
+
+/// Settings of a single day, the `[dayNN]` section of the config
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Settings(BTreeMap<String, String>);
+
+impl Settings {
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// Error on any setting not among `known`
+    pub(crate) fn check(&self, day: u8, known: &[&str]) -> Res<()> {
+        match self.0.keys().find(|key| !known.contains(&key.as_str())) {
+            Some(key) => Err(format!("unknown setting 'day{day:02}.{key}'")),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the `number` setting asks for [`BigInt`] over machine integers
+    pub(crate) fn big(&self) -> Res<bool> {
+        match self.get("number") {
+            None | Some("machine") => Ok(false),
+            Some("big") => Ok(true),
+            Some(other) => Err(format!("invalid number '{other}', expected machine or big")),
+        }
+    }
+
+    /// The `variant` setting, which must be one of the variants of `part`
+    pub(crate) fn variant(&self, solution: &dyn Solution, part: u8) -> Res<Option<&str>> {
+        let Some(name) = self.get("variant") else {
+            return Ok(None);
+        };
+        let names = solution
+            .variants()
+            .iter()
+            .filter(|variant| variant.part == part)
+            .map(|variant| variant.name)
+            .collect::<Vec<_>>();
+        match names.contains(&name) {
+            true => Ok(Some(name)),
+            false if names.is_empty() => Err(format!(
+                "day {} part {part} has no variants",
+                solution.day()
+            )),
+            false => Err(format!(
+                "day {} part {part} has no variant '{name}', expected one of {}",
+                solution.day(),
+                names.join(", ")
+            )),
+        }
+    }
+}
+
+/// A named alternative to the puzzle rules of a part
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Variant {
+    pub(crate) part: u8,
+    pub(crate) name: &'static str,
+    pub(crate) help: &'static str,
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Settings {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+pub(crate) trait Solution: Sync {
+    /// Puzzle day, starting at 1
+    fn day(&self) -> u8;
+
+    /// Number of implemented parts
+    fn parts(&self) -> u8 {
+        2
+    }
+
+    /// Keys of the `[dayNN]` section the day reads
+    fn settings(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Alternative rules picked with the `variant` setting
+    fn variants(&self) -> &'static [Variant] {
+        &[]
+    }
+
+    /// Solve a single part, starting at 1
+    fn solve(&self, part: u8, input: &str) -> Res<Answer>;
+
+    /// Solve a single part with the settings of the day, which most days ignore
+    fn solve_with(&self, part: u8, input: &str, _settings: &Settings) -> Res<Answer> {
+        self.solve(part, input)
+    }
+
+    /// Report of how a part is solved, for the days that can explain themselves
+    fn explain(&self, _part: u8, _input: &str, _settings: &Settings) -> Option<Res<String>> {
+        None
+    }
+}
+
+pub(crate) fn missing_part(day: u8, part: u8) -> Res<Answer> {
+    Err(format!("day {day} has no part {part}"))
+}
+
+pub(crate) const SOLUTIONS: &[&dyn Solution] = &[
+    &crate::day01::Day01,
+    &crate::day02::Day02,
+    &crate::day03::Day03,
+    &crate::day04::Day04,
+    &crate::day05::Day05,
+    &crate::day06::Day06,
+    &crate::day07::Day07,
+    &crate::day08::Day08,
+    &crate::day09::Day09,
+    &crate::day10::Day10,
+    &crate::day11::Day11,
+    &crate::day12::Day12,
+    &crate::day13::Day13,
+    &crate::day14::Day14,
+];
+
+pub(crate) fn find(day: u8) -> Option<&'static dyn Solution> {
+    SOLUTIONS
+        .iter()
+        .copied()
+        .find(|solution| solution.day() == day)
+}
@@ -0,0 +1,115 @@
+//! Advent calendar of what is solved, what inputs exist and which answers check out.
+
+use std::path::Path;
+
+use crate::config::{self, Config};
+use crate::manifest::{self, Manifest};
+use crate::runner;
+use crate::solution::{self, Res};
+use crate::store;
+
+const DAYS: u8 = 25;
+
+enum Check {
+    Missing,
+    Unchecked,
+    Passed(usize),
+    Failed(usize, usize),
+}
+
+impl Check {
+    fn cell(&self) -> String {
+        match self {
+            Self::Missing => "-".to_string(),
+            Self::Unchecked => "?".to_string(),
+            Self::Passed(count) => format!("ok {count}/{count}"),
+            Self::Failed(passed, count) => format!("FAIL {passed}/{count}"),
+        }
+    }
+}
+
+/// Compare a part against every expected answer of the manifest
+fn check(day: u8, part: u8, manifest: &Manifest, config: &Config) -> Res<(Check, bool)> {
+    let Some(solution) = solution::find(day).filter(|s| part <= s.parts()) else {
+        return Ok((Check::Missing, false));
+    };
+    let input = config.day_path("input", day);
+    let dir = input.parent().unwrap_or(Path::new("."));
+    let limit = config.time_limit()?;
+
+    let mut count = 0;
+    let mut passed = 0;
+    let mut star = false;
+    for (stem, expected) in manifest.inputs(day, part) {
+        let path = match stem {
+            "input" => input.clone(),
+            stem => dir.join(format!("{stem}.txt")),
+        };
+        if !store::exists(&path) {
+            continue;
+        }
+        count += 1;
+        let answer = runner::solve_file(solution, part, &path, limit);
+        if answer.is_ok_and(|answer| answer.to_string() == expected) {
+            passed += 1;
+            star |= stem == "input";
+        }
+    }
+    let check = match (count, passed) {
+        (0, _) => Check::Unchecked,
+        (count, passed) if count == passed => Check::Passed(count),
+        (count, passed) => Check::Failed(passed, count),
+    };
+    Ok((check, star))
+}
+
+/// One row per day of the calendar, from the solution registry and the manifest
+pub(crate) fn status(config: &Config) -> Res<String> {
+    let manifest = Manifest::load(&config::root().join(manifest::FILE_NAME))?;
+    let mut rows = vec![[
+        "day", "module", "part1", "part2", "input", "samples", "stars",
+    ]
+    .map(String::from)];
+    let mut stars = 0;
+    for day in 1..=DAYS {
+        let input = config.day_path("input", day);
+        let dir = input.parent().unwrap_or(Path::new("."));
+        let (part1, star1) = check(day, 1, &manifest, config)?;
+        let (part2, star2) = check(day, 2, &manifest, config)?;
+        let earned = star1 as usize + star2 as usize;
+        stars += earned;
+        rows.push([
+            format!("{day:02}"),
+            if solution::find(day).is_some() {
+                "yes"
+            } else {
+                "-"
+            }
+            .to_string(),
+            part1.cell(),
+            part2.cell(),
+            if store::exists(&input) { "yes" } else { "-" }.to_string(),
+            match runner::samples(dir).len() {
+                0 => "-".to_string(),
+                count => count.to_string(),
+            },
+            "*".repeat(earned),
+        ]);
+    }
+
+    let widths: [usize; 7] =
+        std::array::from_fn(|col| rows.iter().map(|row| row[col].len()).max().unwrap_or(0));
+    let mut out = String::new();
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out += line.trim_end();
+        out.push('\n');
+    }
+    out += &format!("verified stars: {stars}/{}\n", DAYS as usize * 2);
+    Ok(out)
+}
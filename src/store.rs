@@ -0,0 +1,211 @@
+//! Encrypted-at-rest storage of private puzzle inputs.
+//!
+//! An input `input.txt` may be stored as `input.txt.enc`, encrypted with ChaCha20
+//! under the key in [`KEY_FILE`] at the repository root. The key file is ignored
+//! by git and never leaves the machine. Without the key an encrypted input counts as missing, so the
+//! samples are used instead.
+//!
+//! `inputs encrypt` creates the key on first use and replaces every plain input
+//! by its `.enc` twin, which can then be committed. Copy the key file to other
+//! machines by hand. Plain inputs committed before stay in the git history,
+//! encrypting does not remove them from there.
+
+use std::io::{Error, Read, Result, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+const KEY_FILE: &str = ".aoc-key";
+
+const MAGIC: &[u8; 4] = b"AOC1";
+/// Zero block encrypted ahead of the text, to tell a wrong key from a broken file
+const CHECK: [u8; 8] = [0; 8];
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+fn words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    std::array::from_fn(|i| u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap()))
+}
+
+/// ChaCha20 as in RFC 8439, encryption and decryption are the same operation
+fn chacha20(key: &[u8; 32], nonce: &[u8; 12], counter: u32, data: &mut [u8]) {
+    let key: [u32; 8] = words(key);
+    let nonce: [u32; 3] = words(nonce);
+    for (block, chunk) in data.chunks_mut(64).enumerate() {
+        let mut init = [0u32; 16];
+        init[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+        init[4..12].copy_from_slice(&key);
+        init[12] = counter.wrapping_add(block as u32);
+        init[13..].copy_from_slice(&nonce);
+
+        let mut state = init;
+        for _ in 0..10 {
+            quarter_round(&mut state, 0, 4, 8, 12);
+            quarter_round(&mut state, 1, 5, 9, 13);
+            quarter_round(&mut state, 2, 6, 10, 14);
+            quarter_round(&mut state, 3, 7, 11, 15);
+            quarter_round(&mut state, 0, 5, 10, 15);
+            quarter_round(&mut state, 1, 6, 11, 12);
+            quarter_round(&mut state, 2, 7, 8, 13);
+            quarter_round(&mut state, 3, 4, 9, 14);
+        }
+        let stream = state
+            .iter()
+            .zip(init)
+            .flat_map(|(word, init)| word.wrapping_add(init).to_le_bytes());
+        for (byte, key) in chunk.iter_mut().zip(stream) {
+            *byte ^= key;
+        }
+    }
+}
+
+fn random<const N: usize>() -> Result<[u8; N]> {
+    let mut out = [0; N];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut out)?;
+    Ok(out)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The key file, found at the repository root whatever the working directory
+pub(crate) fn key_path() -> PathBuf {
+    config::root().join(KEY_FILE)
+}
+
+fn read_key() -> Result<[u8; 32]> {
+    let path = key_path();
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+    let text = text.trim();
+    let bytes = (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            text.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect::<Option<Vec<_>>>();
+    bytes
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::other(format!("{}: expected 64 hex digits", path.display())))
+}
+
+/// Load the key, creating a fresh one readable by the owner only on first use
+fn key_or_create() -> Result<[u8; 32]> {
+    let path = key_path();
+    if !path.exists() {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        file.write_all((hex(&random::<32>()?) + "\n").as_bytes())?;
+    }
+    read_key()
+}
+
+fn encrypted(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".enc");
+    PathBuf::from(name)
+}
+
+fn encrypt(key: &[u8; 32], text: &[u8]) -> Result<Vec<u8>> {
+    let nonce = random::<12>()?;
+    let mut body = [&CHECK[..], text].concat();
+    chacha20(key, &nonce, 1, &mut body);
+    Ok([&MAGIC[..], &nonce, &body].concat())
+}
+
+fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>> {
+    let body = data
+        .strip_prefix(MAGIC)
+        .filter(|rest| rest.len() >= 12 + CHECK.len())
+        .ok_or_else(|| Error::other("not an encrypted input"))?;
+    let (nonce, body) = body.split_at(12);
+    let mut body = body.to_vec();
+    chacha20(key, nonce.try_into().unwrap(), 1, &mut body);
+    if body[..CHECK.len()] != CHECK {
+        return Err(Error::other(format!(
+            "wrong key in {}",
+            key_path().display()
+        )));
+    }
+    Ok(body.split_off(CHECK.len()))
+}
+
+/// Whether `path` can be read, in plain text or encrypted with a key at hand
+pub(crate) fn exists(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    path.is_file() || (encrypted(path).is_file() && key_path().is_file())
+}
+
+/// Read `path`, decrypting its `.enc` twin if only that is present
+pub(crate) fn read(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    if path.is_file() {
+        return std::fs::read_to_string(path);
+    }
+    let enc = encrypted(path);
+    let data = std::fs::read(&enc)?;
+    let text = decrypt(&read_key()?, &data)
+        .map_err(|e| Error::other(format!("{}: {e}", enc.display())))?;
+    String::from_utf8(text).map_err(Error::other)
+}
+
+/// Replace every plain input of `paths` by its encrypted twin, skipping missing ones
+pub(crate) fn encrypt_inputs(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let key = key_or_create()?;
+    let mut done = Vec::new();
+    for path in paths {
+        if !path.is_file() {
+            continue;
+        }
+        let text = std::fs::read(path)?;
+        let enc = encrypted(path);
+        std::fs::write(&enc, encrypt(&key, &text)?)?;
+        // Only drop the plain text once the copy is known to decrypt
+        if decrypt(&key, &std::fs::read(&enc)?)? != text {
+            return Err(Error::other(format!(
+                "{}: verification failed",
+                enc.display()
+            )));
+        }
+        std::fs::remove_file(path)?;
+        done.push(enc);
+    }
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc8439() {
+        // Test vector of section 2.4.2
+        let key = std::array::from_fn(|i| i as u8);
+        let nonce = [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let mut text = *b"Ladies and Gentlemen of the class of '99: ";
+        chacha20(&key, &nonce, 1, &mut text);
+        assert_eq!(hex(&text[..16]), "6e2e359a2568f98041ba0728dd0d6981");
+    }
+
+    #[test]
+    fn roundtrip() {
+        let key = [7; 32];
+        let data = encrypt(&key, b"seeds: 79 14 55 13").unwrap();
+        assert_eq!(decrypt(&key, &data).unwrap(), b"seeds: 79 14 55 13");
+        assert!(decrypt(&[8; 32], &data).is_err());
+    }
+}